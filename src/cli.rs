@@ -1,5 +1,7 @@
 use clap::Parser;
 
+use crate::filter;
+
 #[derive(Parser, Debug, Clone)]
 #[command(
     name = "lsf",
@@ -34,6 +36,44 @@ pub struct Args {
     #[arg(long = "no-icons")]
     pub no_icons: bool,
 
+    /// Icon set: auto, nerd-v3, nerd-v2 (Nerd Fonts 2.x), emoji, ascii or text
+    /// (screen-reader friendly labels). auto uses nerd-v3, or ascii on the Linux
+    /// console or a non-UTF-8 locale; it cannot tell which glyphs your font has,
+    /// so set LSF_ICONS (environment or lsf.conf) to pick one [default: auto]
+    #[arg(
+        long = "icons",
+        value_name = "SET",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "auto",
+        value_parser = ["auto", "nerd-v3", "nerd-v2", "emoji", "ascii", "text"]
+    )]
+    pub icons: Option<String>,
+
+    /// Terminal cells each icon takes: 1, 2 or auto (the Unicode width, or 2
+    /// when a cursor position query shows the terminal advancing two cells
+    /// per Nerd Font glyph; only asked when stdin and stdout are a terminal)
+    #[arg(
+        long = "icon-width",
+        value_name = "WIDTH",
+        default_value = "auto",
+        hide_default_value = true,
+        value_parser = ["auto", "1", "2"]
+    )]
+    pub icon_width: String,
+
+    /// How icons reach --icon-width: fill (pad with spaces, for terminals that
+    /// draw wide glyphs over the next cell) or none (the terminal advances the
+    /// full width itself) [default: fill]
+    #[arg(
+        long = "icon-padding",
+        value_name = "MODE",
+        default_value = "fill",
+        hide_default_value = true,
+        value_parser = ["fill", "none"]
+    )]
+    pub icon_padding: String,
+
     /// Sort by file size, largest first
     #[arg(short = 'S')]
     pub sort_size: bool,
@@ -54,6 +94,22 @@ pub struct Args {
     #[arg(short = 'v')]
     pub version_sort: bool,
 
+    /// Sort by KEY[,KEY...]: name, size, time, atime, ctime, birth, extension, version,
+    /// width, inode, type, owner, group, git-status or none; prefix a key with '-' to reverse it
+    #[arg(
+        long = "sort",
+        value_name = "KEY[,KEY...]",
+        value_delimiter = ',',
+        allow_hyphen_values = true,
+        value_parser = parse_sort_spec
+    )]
+    pub sort: Option<Vec<SortSpec>>,
+
+    /// How names are compared: c (byte order), locale (LC_COLLATE) or natural
+    /// [default: locale if LC_ALL/LC_COLLATE/LANG is set, else case-insensitive]
+    #[arg(long = "collate", value_name = "MODE", value_parser = ["c", "locale", "natural"])]
+    pub collate: Option<String>,
+
     /// Reverse order while sorting
     #[arg(short = 'r', long = "reverse")]
     pub reverse: bool,
@@ -82,9 +138,21 @@ pub struct Args {
     #[arg(short = 'p')]
     pub slash_dirs: bool,
 
-    /// Print the allocated number of blocks for each file
-    #[arg(short = 's', long = "size")]
-    pub show_size: bool,
+    /// Print the allocated number of blocks for each file. With a value, list
+    /// only entries larger (+N) or smaller (-N) than N, or of size N; N takes a
+    /// k, M, G or T suffix (e.g. --size=+10M) and sizes are rounded up to that
+    /// unit first, as in find(1), so --size=-1k matches only empty files
+    #[arg(
+        short = 's',
+        long = "size",
+        value_name = "[+-]N",
+        require_equals = true,
+        num_args = 0..=1,
+        allow_hyphen_values = true,
+        overrides_with = "size",
+        value_parser = filter::parse_size
+    )]
+    pub size: Option<Option<filter::SizeBound>>,
 
     /// Print the inode number of each file
     #[arg(short = 'i', long = "inode")]
@@ -146,14 +214,189 @@ pub struct Args {
     #[arg(short = 'B', long = "ignore-backups")]
     pub ignore_backups: bool,
 
+    /// Time/date format with -l: full-iso, long-iso, iso, locale (the default:
+    /// month, day and time in the LC_TIME day/month order), relative or +FORMAT
+    /// (FORMAT is interpreted like strftime; '+OLD\nRECENT' sets separate
+    /// formats for old and recent files)
+    #[arg(long = "time-style", value_name = "TIME_STYLE")]
+    pub time_style: Option<String>,
+
+    /// With -l, color timestamps and sizes on a gradient so recent and large
+    /// files stand out [all, age, size]
+    #[arg(
+        long = "color-scale",
+        value_name = "FIELD",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "all",
+        value_parser = ["all", "age", "size"]
+    )]
+    pub color_scale: Option<String>,
+
+    /// Scale --color-scale to the entries in each listing (relative) or to
+    /// fixed ranges: a minute to a year, 1 KiB to 1 GiB (absolute)
+    #[arg(
+        long = "color-scale-mode",
+        value_name = "MODE",
+        default_value = "relative",
+        hide_default_value = true,
+        value_parser = ["relative", "absolute"]
+    )]
+    pub color_scale_mode: String,
+
     /// With -l, print the author of each file
     #[arg(long = "author")]
     pub author: bool,
 
+    /// With -l, show these columns in this order: inode, blocks, icon, perms, attributes,
+    /// nlink, user, group, author, context, size, mtime, atime, ctime, birth, caps, mime,
+    /// git, name (also read from LSF_COLUMNS or lsf.conf)
+    #[arg(
+        long = "columns",
+        value_name = "COL[,COL...]",
+        value_delimiter = ',',
+        value_parser = parse_column
+    )]
+    pub columns: Option<Vec<Column>>,
+
+    /// With -l, print a header line naming the columns (repeated for each
+    /// directory with -R)
+    #[arg(long = "header")]
+    pub header: bool,
+
+    /// Show the number of entries in each directory: in the long-view size
+    /// column (size), as a 'name (N)' suffix in grid and tree views (name), or both (all)
+    #[arg(
+        long = "dir-count",
+        value_name = "WHERE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "size",
+        value_parser = ["size", "name", "all"]
+    )]
+    pub dir_count: Option<String>,
+
+    /// After each directory, print counts by type, the total size and the N most
+    /// common extensions (default 5); with -R or --tree, also a grand total
+    #[arg(
+        long = "summary",
+        value_name = "N",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "5"
+    )]
+    pub summary: Option<usize>,
+
+    /// With -l, show permissions as symbolic (rwxr-xr-x), octal (0755) or both
+    #[arg(
+        long = "permissions",
+        value_name = "STYLE",
+        default_value = "symbolic",
+        hide_default_value = true,
+        value_parser = ["symbolic", "octal", "both"]
+    )]
+    pub permissions: String,
+
+    /// With -l, show inode attribute flags like lsattr: i(mmutable), a(ppend only),
+    /// d (no dump), c(ompressed), E(ncrypted), V(erity), x (DAX), M(ount root)
+    #[arg(long = "attributes")]
+    pub attributes: bool,
+
+    /// With -l, list extended attributes beneath each entry: names, sizes
+    /// or values [default: sizes]
+    #[arg(
+        long = "xattr",
+        value_name = "DETAIL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "sizes",
+        value_parser = ["names", "sizes", "values"]
+    )]
+    pub xattr: Option<String>,
+
+    /// With -l, list POSIX ACL entries beneath each entry
+    #[arg(long = "acl")]
+    pub acl: bool,
+
+    /// With -l, show Linux file capabilities (security.capability)
+    #[arg(long = "caps")]
+    pub caps: bool,
+
+    /// Detect file types from content (shebang lines, executable headers,
+    /// magic numbers) to pick icons and colors. This opens and reads the
+    /// first 512 bytes of every regular file listed, which is slow on large
+    /// directories and network filesystems
+    #[arg(long = "sniff")]
+    pub sniff: bool,
+
+    /// With -l, show each file's MIME type, detected from content (implies
+    /// --sniff, so every regular file listed is opened and read)
+    #[arg(long = "mime")]
+    pub mime: bool,
+
+    /// List only risky entries, recursively: setuid/setgid files, world-writable
+    /// entries (except sticky directories), files with capabilities and
+    /// group-writable entries owned by someone other than you or root
+    #[arg(long = "audit")]
+    pub audit: bool,
+
+    /// List only directories (with -R or --tree, still descending into all of them)
+    #[arg(long = "only-dirs")]
+    pub only_dirs: bool,
+
+    /// List only regular files
+    #[arg(long = "only-files")]
+    pub only_files: bool,
+
+    /// List only entries of these types: f (file), d (directory), l (symlink),
+    /// p (fifo), s (socket), b (block device) or c (character device)
+    #[arg(
+        long = "type",
+        value_name = "TYPE[,TYPE...]",
+        value_delimiter = ',',
+        value_parser = ["f", "d", "l", "p", "s", "b", "c"]
+    )]
+    pub file_type: Option<Vec<String>>,
+
+    /// List only entries modified more recently than FILE
+    #[arg(long = "newer", value_name = "FILE")]
+    pub newer: Option<String>,
+
+    /// List only entries changed (ctime: content, permissions or ownership, as
+    /// with find -ctime) within DURATION, e.g. 30m, 2d or 1w
+    #[arg(
+        long = "changed-within",
+        value_name = "DURATION",
+        value_parser = filter::parse_duration
+    )]
+    pub changed_within: Option<std::time::Duration>,
+
+    /// List only entries owned by USER (a name or numeric ID)
+    #[arg(long = "owner", value_name = "USER")]
+    pub owner: Option<String>,
+
+    /// List only entries with permission bits MODE (octal); -MODE matches
+    /// entries with all of those bits set, /MODE with any of them
+    #[arg(
+        long = "perm",
+        value_name = "MODE",
+        allow_hyphen_values = true,
+        value_parser = filter::parse_perm
+    )]
+    pub perm: Option<filter::PermMatch>,
+
     /// Colorize the output [auto, always, never]
     #[arg(long = "color", default_value = "auto", hide_default_value = true)]
     pub color: String,
 
+    /// Color theme: default, solarized, dracula or high-contrast
+    #[arg(
+        long = "theme",
+        value_name = "NAME",
+        value_parser = ["default", "solarized", "dracula", "high-contrast"]
+    )]
+    pub theme: Option<String>,
+
     /// Do not colorize the output (same as --color=never)
     #[arg(long = "no-color")]
     pub no_color: bool,
@@ -197,6 +440,151 @@ pub struct Args {
     /// Hide entries matching pattern (overridden by -a or -A)
     #[arg(long = "hide")]
     pub hide_pattern: Option<String>,
+
+    /// Truncate names longer than N columns in grid, comma and tree views
+    /// ('auto' truncates only what would overflow the terminal)
+    #[arg(long = "max-name-width", value_name = "N|auto", value_parser = parse_name_width)]
+    pub max_name_width: Option<NameWidth>,
+}
+
+/// Limit applied to file names by `--max-name-width`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameWidth {
+    /// Truncate to a fixed number of columns
+    Fixed(usize),
+    /// Truncate to whatever is left of the terminal width
+    Auto,
+}
+
+/// A field entries can be sorted by with `--sort`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    None,
+    Name,
+    Size,
+    Time,
+    Atime,
+    Ctime,
+    Birth,
+    Extension,
+    Version,
+    Width,
+    Inode,
+    Type,
+    Owner,
+    Group,
+    GitStatus,
+}
+
+impl SortField {
+    /// Whether comparing by this field needs stat() metadata.
+    pub fn needs_metadata(self) -> bool {
+        matches!(
+            self,
+            SortField::Size
+                | SortField::Time
+                | SortField::Atime
+                | SortField::Ctime
+                | SortField::Birth
+                | SortField::Owner
+                | SortField::Group
+        )
+    }
+}
+
+/// One `--sort` key, optionally reversed with a leading '-'.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortSpec {
+    pub field: SortField,
+    pub reverse: bool,
+}
+
+fn parse_sort_spec(s: &str) -> Result<SortSpec, String> {
+    let (reverse, key) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+    let field = match key {
+        "none" => SortField::None,
+        "name" => SortField::Name,
+        "size" => SortField::Size,
+        "time" | "mtime" => SortField::Time,
+        "atime" => SortField::Atime,
+        "ctime" => SortField::Ctime,
+        "birth" => SortField::Birth,
+        "extension" => SortField::Extension,
+        "version" => SortField::Version,
+        "width" => SortField::Width,
+        "inode" => SortField::Inode,
+        "type" => SortField::Type,
+        "owner" => SortField::Owner,
+        "group" => SortField::Group,
+        "git-status" | "git" => SortField::GitStatus,
+        _ => return Err(format!("invalid sort key '{}'", key)),
+    };
+    Ok(SortSpec { field, reverse })
+}
+
+/// A long-view column, selected and ordered with `--columns`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Inode,
+    Blocks,
+    Icon,
+    Perms,
+    Attributes,
+    Links,
+    User,
+    Group,
+    Author,
+    Context,
+    Size,
+    Mtime,
+    Atime,
+    Ctime,
+    Birth,
+    Caps,
+    Mime,
+    Git,
+    Name,
+}
+
+pub fn parse_column(s: &str) -> Result<Column, String> {
+    Ok(match s.trim() {
+        "inode" => Column::Inode,
+        "blocks" => Column::Blocks,
+        "icon" => Column::Icon,
+        "perms" | "permissions" | "mode" => Column::Perms,
+        "attributes" | "attrs" => Column::Attributes,
+        "nlink" | "links" => Column::Links,
+        "user" | "owner" => Column::User,
+        "group" => Column::Group,
+        "author" => Column::Author,
+        "context" => Column::Context,
+        "size" => Column::Size,
+        "mtime" | "time" | "modified" => Column::Mtime,
+        "atime" | "accessed" => Column::Atime,
+        "ctime" | "changed" => Column::Ctime,
+        "birth" | "created" => Column::Birth,
+        "caps" => Column::Caps,
+        "mime" => Column::Mime,
+        "git" => Column::Git,
+        "name" => Column::Name,
+        other => return Err(format!("invalid column '{}'", other)),
+    })
+}
+
+fn parse_name_width(s: &str) -> Result<NameWidth, String> {
+    if s == "auto" {
+        return Ok(NameWidth::Auto);
+    }
+    match s.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(NameWidth::Fixed(n)),
+        _ => Err(format!(
+            "invalid width '{}' (expected a positive number or 'auto')",
+            s
+        )),
+    }
 }
 
 impl Args {
@@ -215,6 +603,102 @@ impl Args {
         self.long || self.long_no_group || self.long_no_owner || self.numeric_uid_gid
     }
 
+    /// Whether subdirectories are listed too (-R, or implied by --audit)
+    pub fn is_recursive(&self) -> bool {
+        self.recursive || self.audit
+    }
+
+    /// The long-view columns in display order: `--columns` (or LSF_COLUMNS /
+    /// lsf.conf) if set, else the columns implied by the other flags. The
+    /// name is always shown, and the icon goes before it unless placed.
+    pub fn long_columns(&self, show_icons: bool) -> Vec<Column> {
+        let mut columns = match self.columns {
+            Some(ref columns) => {
+                let mut columns: Vec<Column> = columns
+                    .iter()
+                    .copied()
+                    .filter(|&c| c != Column::Icon || show_icons)
+                    .collect();
+                if !columns.contains(&Column::Name) {
+                    columns.push(Column::Name);
+                }
+                if show_icons && !columns.contains(&Column::Icon) {
+                    let name = columns.iter().position(|&c| c == Column::Name).unwrap_or(0);
+                    columns.insert(name, Column::Icon);
+                }
+                columns
+            }
+            None => {
+                let flags = [
+                    (Column::Inode, self.inode),
+                    (Column::Blocks, self.show_size()),
+                    (Column::Icon, show_icons),
+                    (Column::Perms, true),
+                    (Column::Attributes, self.attributes),
+                    (Column::Links, true),
+                    (Column::User, !self.long_no_owner),
+                    (Column::Group, !self.long_no_group && !self.no_group),
+                    (Column::Author, self.author),
+                    (Column::Context, self.context),
+                    (Column::Size, true),
+                    (Column::Mtime, true),
+                    (Column::Caps, self.caps),
+                    (Column::Mime, self.mime),
+                    (Column::Name, true),
+                    (Column::Git, self.git),
+                ];
+                flags
+                    .iter()
+                    .filter(|(_, on)| *on)
+                    .map(|(c, _)| *c)
+                    .collect()
+            }
+        };
+        // Keep the first of any repeated column
+        let mut seen = Vec::with_capacity(columns.len());
+        columns.retain(|&c| {
+            let first = !seen.contains(&c);
+            seen.push(c);
+            first
+        });
+        columns
+    }
+
+    /// Whether `-s`/`--size` without a bound asks for the block count column.
+    pub fn show_size(&self) -> bool {
+        matches!(self.size, Some(None))
+    }
+
+    /// The `--size=BOUND` filter, if one was given.
+    pub fn size_filter(&self) -> Option<filter::SizeBound> {
+        self.size.flatten()
+    }
+
+    /// Whether long view shows directory entry counts as their size.
+    pub fn count_in_size(&self) -> bool {
+        matches!(self.dir_count.as_deref(), Some("size" | "all"))
+    }
+
+    /// Whether grid and tree views append `(N)` to directory names.
+    pub fn count_in_name(&self) -> bool {
+        matches!(self.dir_count.as_deref(), Some("name" | "all"))
+    }
+
+    /// Whether git status is needed: `--git`, a git-status sort key, or a git
+    /// column in long view.
+    pub fn show_git(&self) -> bool {
+        self.git
+            || self
+                .sort_specs()
+                .iter()
+                .any(|k| k.field == SortField::GitStatus)
+            || (self.is_long()
+                && self
+                    .columns
+                    .as_ref()
+                    .is_some_and(|c| c.contains(&Column::Git)))
+    }
+
     /// Whether to use color output
     pub fn use_color(&self) -> bool {
         if self.no_color {
@@ -224,18 +708,166 @@ impl Args {
             "always" | "yes" | "force" => true,
             "never" | "no" | "none" => false,
             _ => {
-                // "auto" - check if stdout is a tty
-                atty_check()
+                // "auto": NO_COLOR and CLICOLOR=0 disable color, CLICOLOR_FORCE
+                // enables it even when not writing to a tty
+                let env_set = |name: &str| std::env::var_os(name).is_some_and(|v| !v.is_empty());
+                if env_set("NO_COLOR") {
+                    false
+                } else if env_set("CLICOLOR_FORCE")
+                    && std::env::var_os("CLICOLOR_FORCE").is_some_and(|v| v != "0")
+                {
+                    true
+                } else if std::env::var_os("CLICOLOR").is_some_and(|v| v == "0") {
+                    false
+                } else {
+                    atty_check()
+                }
             }
         }
     }
 
     /// Whether sorting is disabled
     pub fn no_sort(&self) -> bool {
-        self.unsorted || self.no_sort_all
+        self.unsorted
+            || self.no_sort_all
+            || self
+                .sort
+                .as_ref()
+                .is_some_and(|keys| keys.iter().all(|k| k.field == SortField::None))
+    }
+
+    /// The sort keys in effect: `--sort` if given, else the one implied by -S/-t/-X/-v.
+    pub fn sort_specs(&self) -> Vec<SortSpec> {
+        if let Some(ref keys) = self.sort {
+            return keys
+                .iter()
+                .copied()
+                .filter(|k| k.field != SortField::None)
+                .collect();
+        }
+
+        let field = if self.sort_size {
+            SortField::Size
+        } else if self.sort_time {
+            SortField::Time
+        } else if self.sort_extension {
+            SortField::Extension
+        } else if self.version_sort {
+            SortField::Version
+        } else {
+            // Default: alphabetical (case-insensitive)
+            SortField::Name
+        };
+        vec![SortSpec {
+            field,
+            reverse: false,
+        }]
+    }
+
+    /// Whether every entry needs a full stat() rather than just the readdir
+    /// file type. Colors and icons only need the permission bits of some
+    /// entries; directory listings stat those individually.
+    pub fn needs_metadata(&self) -> bool {
+        self.is_long()
+            || self.sort_specs().iter().any(|k| k.field.needs_metadata())
+            || self.show_size()
+            || self.classify
+            || self.dereference
+            || self.audit
+            || self.sniff_content()
+            || self.summary.is_some()
+            || self.has_filters()
+    }
+
+    /// Whether any of the type, size, time, owner or permission filters is set.
+    pub fn has_filters(&self) -> bool {
+        self.only_dirs
+            || self.only_files
+            || self.file_type.is_some()
+            || self.size_filter().is_some()
+            || self.newer.is_some()
+            || self.changed_within.is_some()
+            || self.owner.is_some()
+            || self.perm.is_some()
+    }
+
+    /// Whether long view needs inode attribute flags: for the attributes
+    /// column, or to highlight immutable files in color.
+    pub fn needs_attributes(&self) -> bool {
+        self.is_long()
+            && (self.use_color() || self.long_columns(false).contains(&Column::Attributes))
+    }
+
+    /// Whether regular files are read to detect their type.
+    pub fn sniff_content(&self) -> bool {
+        self.sniff
+            || self.mime
+            || (self.is_long()
+                && self
+                    .columns
+                    .as_ref()
+                    .is_some_and(|c| c.contains(&Column::Mime)))
+    }
+
+    /// Maximum display width for a file name, given the width already taken
+    /// by the rest of the line. Returns None when names must not be truncated.
+    pub fn name_width_limit(&self, term_width: usize, used: usize) -> Option<usize> {
+        match self.max_name_width? {
+            NameWidth::Fixed(n) => Some(n),
+            NameWidth::Auto => Some(term_width.saturating_sub(used).max(1)),
+        }
     }
 }
 
 fn atty_check() -> bool {
     unsafe { libc::isatty(libc::STDOUT_FILENO) != 0 }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sort_spec_parsing() {
+        let cases = [
+            ("name", Some((SortField::Name, false))),
+            ("-size", Some((SortField::Size, true))),
+            ("mtime", Some((SortField::Time, false))),
+            ("-time", Some((SortField::Time, true))),
+            ("git", Some((SortField::GitStatus, false))),
+            ("-git-status", Some((SortField::GitStatus, true))),
+            ("none", Some((SortField::None, false))),
+            ("", None),
+            ("-", None),
+            ("--size", None),
+            ("Size", None),
+            ("sizes", None),
+        ];
+        for (s, expected) in cases {
+            let parsed = parse_sort_spec(s)
+                .ok()
+                .map(|spec| (spec.field, spec.reverse));
+            assert_eq!(parsed, expected, "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn sort_specs_from_args() {
+        let specs = |argv: &[&str]| {
+            Args::parse_from(std::iter::once("lsf").chain(argv.iter().copied()))
+                .sort_specs()
+                .iter()
+                .map(|spec| (spec.field, spec.reverse))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(specs(&[]), [(SortField::Name, false)]);
+        assert_eq!(specs(&["-S"]), [(SortField::Size, false)]);
+        assert_eq!(
+            specs(&["--sort=-size,extension"]),
+            [(SortField::Size, true), (SortField::Extension, false)]
+        );
+        // "none" keys are dropped, leaving ties to the name
+        assert_eq!(specs(&["--sort=none,type"]), [(SortField::Type, false)]);
+        assert!(Args::try_parse_from(["lsf", "--sort=size,bogus"]).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::cli::{self, Args};
+use crate::icons;
+
+/// Path of the configuration file: `$XDG_CONFIG_HOME/lsf/lsf.conf`, or
+/// `~/.config/lsf/lsf.conf`.
+fn config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config")))?;
+    Some(base.join("lsf").join("lsf.conf"))
+}
+
+/// Read the plain `NAME=value` assignments from the configuration file.
+/// The file is shell syntax (it is shared with the original shell version of
+/// lsf), so comments, `export` and quotes are allowed; anything else, such as
+/// the `ICONS[...]` array entries, is ignored.
+fn read_settings() -> HashMap<String, String> {
+    let mut settings = HashMap::new();
+    let contents = match config_path().and_then(|p| std::fs::read_to_string(p).ok()) {
+        Some(c) => c,
+        None => return settings,
+    };
+
+    for line in contents.lines() {
+        let line = line.trim();
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (name, value) = match line.split_once('=') {
+            Some(kv) => kv,
+            None => continue,
+        };
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            continue;
+        }
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+            .unwrap_or(value);
+        settings.insert(name.to_string(), value.to_string());
+    }
+    settings
+}
+
+/// Fill in options not given on the command line from the environment and
+/// the configuration file, in that order. Invalid values are reported and
+/// ignored.
+pub fn apply(args: &mut Args) {
+    let mut settings = None;
+    let mut lookup = |name: &'static str| match std::env::var(name) {
+        Ok(v) if !v.is_empty() => Some((v, name)),
+        _ => settings
+            .get_or_insert_with(read_settings)
+            .remove(name)
+            .filter(|v| !v.is_empty())
+            .map(|v| (v, "lsf.conf")),
+    };
+
+    if args.columns.is_none() {
+        if let Some((spec, source)) = lookup("LSF_COLUMNS") {
+            match spec.split(',').map(cli::parse_column).collect() {
+                Ok(columns) => args.columns = Some(columns),
+                Err(e) => eprintln!("lsf: {}: {}", source, e),
+            }
+        }
+    }
+
+    if args.icons.as_deref().unwrap_or("auto") == "auto" {
+        if let Some((set, source)) = lookup("LSF_ICONS") {
+            if icons::SET_NAMES.contains(&set.as_str()) {
+                args.icons = Some(set);
+            } else {
+                eprintln!("lsf: {}: invalid icon set '{}'", source, set);
+            }
+        }
+    }
+}
@@ -1,11 +1,19 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fs;
-use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+use std::os::unix::fs::{DirEntryExt, FileTypeExt};
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use std::time::SystemTime;
 
-use crate::cli::Args;
+use crate::cli::{Args, SortField, SortSpec};
+use crate::fileattr::FileAttributes;
 use crate::git::{self, GitRepo, GitStatus};
+use crate::icons;
+use crate::locale::{self, Collation};
+use crate::sniff::{self, ContentType};
+use crate::stat;
+use crate::xattr;
 
 /// Represents a single file/directory entry with all metadata needed for display.
 #[derive(Debug, Clone)]
@@ -25,42 +33,53 @@ pub struct FileEntry {
     pub modified: Option<SystemTime>,
     pub accessed: Option<SystemTime>,
     pub created: Option<SystemTime>,
+    pub changed: Option<SystemTime>,
     pub mode: u32,
     pub nlink: u64,
     pub uid: u32,
     pub gid: u32,
     pub blocks: u64,
     pub inode: u64,
+    /// Device number for block/char devices (st_rdev)
+    pub rdev: u64,
     pub symlink_target: Option<String>,
     pub extension: String,
     pub git_status: Option<GitStatus>,
+    /// Extended attribute names (only loaded for long view)
+    pub xattrs: Vec<String>,
+    /// Inode attribute flags (only loaded for long view)
+    pub attributes: Option<FileAttributes>,
+    /// Type detected from the file's content (only loaded with --sniff/--mime)
+    pub content_type: Option<ContentType>,
+    /// Number of entries in a directory (only loaded with --dir-count)
+    pub child_count: Option<u64>,
 }
 
 impl FileEntry {
     /// Create a FileEntry from a path, reading metadata.
     pub fn from_path(path: &Path, follow_symlinks: bool) -> std::io::Result<Self> {
-        let symlink_meta = fs::symlink_metadata(path)?;
-        let is_symlink = symlink_meta.file_type().is_symlink();
+        let lstat = stat::stat(path, false)?;
+        let is_symlink = lstat.mode & 0o170000 == 0o120000;
 
-        let meta = if follow_symlinks && is_symlink {
-            fs::metadata(path).unwrap_or_else(|_| symlink_meta.clone())
+        let st = if follow_symlinks && is_symlink {
+            stat::stat(path, true).unwrap_or(lstat)
         } else {
-            symlink_meta.clone()
+            lstat
         };
 
-        let file_type = meta.file_type();
         let name = path
             .file_name()
             .map(|n| n.to_string_lossy().into_owned())
             .unwrap_or_else(|| path.to_string_lossy().into_owned());
 
-        let is_dir = file_type.is_dir();
-        let is_executable = !is_dir && (meta.permissions().mode() & 0o111 != 0);
+        let file_type = st.mode & 0o170000;
+        let is_dir = file_type == 0o040000;
+        let is_executable = !is_dir && (st.mode & 0o111 != 0);
         let is_hidden = name.starts_with('.');
-        let is_pipe = file_type.is_fifo();
-        let is_socket = file_type.is_socket();
-        let is_block_device = file_type.is_block_device();
-        let is_char_device = file_type.is_char_device();
+        let is_pipe = file_type == 0o010000;
+        let is_socket = file_type == 0o140000;
+        let is_block_device = file_type == 0o060000;
+        let is_char_device = file_type == 0o020000;
 
         let symlink_target = if is_symlink {
             fs::read_link(path)
@@ -86,22 +105,222 @@ impl FileEntry {
             is_socket,
             is_block_device,
             is_char_device,
-            size: meta.len(),
-            modified: meta.modified().ok(),
-            accessed: meta.accessed().ok(),
-            created: meta.created().ok(),
-            mode: meta.mode(),
-            nlink: meta.nlink(),
-            uid: meta.uid(),
-            gid: meta.gid(),
-            blocks: meta.blocks(),
-            inode: meta.ino(),
+            size: st.size,
+            modified: st.modified,
+            accessed: st.accessed,
+            created: st.created,
+            changed: st.changed,
+            mode: st.mode,
+            nlink: st.nlink,
+            uid: st.uid,
+            gid: st.gid,
+            blocks: st.blocks,
+            inode: st.ino,
+            rdev: st.rdev,
             symlink_target,
             extension,
             git_status: None,
+            xattrs: Vec::new(),
+            attributes: st.attributes,
+            content_type: None,
+            child_count: None,
         })
     }
 
+    /// Create a FileEntry from a directory entry.
+    /// When `need_stat` is false, only the name, inode and file type reported by
+    /// readdir (d_type) are filled in and no stat() call is made; size, times,
+    /// ownership and permission bits are left zeroed.
+    pub fn from_dir_entry(
+        entry: &fs::DirEntry,
+        name: String,
+        follow_symlinks: bool,
+        need_stat: bool,
+        need_link_target: bool,
+    ) -> std::io::Result<Self> {
+        let path = entry.path();
+        if need_stat {
+            return FileEntry::from_path(&path, follow_symlinks);
+        }
+
+        // DirEntry::file_type only falls back to lstat() when d_type is unknown
+        let file_type = entry.file_type()?;
+        let mut fe = FileEntry::bare(name, path);
+
+        fe.is_dir = file_type.is_dir();
+        fe.is_symlink = file_type.is_symlink();
+        fe.is_pipe = file_type.is_fifo();
+        fe.is_socket = file_type.is_socket();
+        fe.is_block_device = file_type.is_block_device();
+        fe.is_char_device = file_type.is_char_device();
+        fe.inode = entry.ino();
+        fe.mode = if fe.is_dir {
+            0o040000
+        } else if fe.is_symlink {
+            0o120000
+        } else if fe.is_pipe {
+            0o010000
+        } else if fe.is_socket {
+            0o140000
+        } else if fe.is_block_device {
+            0o060000
+        } else if fe.is_char_device {
+            0o020000
+        } else {
+            0o100000
+        };
+
+        if fe.is_symlink && need_link_target {
+            fe.symlink_target = fs::read_link(&fe.path)
+                .ok()
+                .map(|t| t.to_string_lossy().into_owned());
+        }
+
+        Ok(fe)
+    }
+
+    /// Create an entry with only a name and path; all metadata is zeroed.
+    fn bare(name: String, path: PathBuf) -> Self {
+        let is_hidden = name.starts_with('.');
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        FileEntry {
+            name,
+            path,
+            is_dir: false,
+            is_symlink: false,
+            is_executable: false,
+            is_hidden,
+            is_pipe: false,
+            is_socket: false,
+            is_block_device: false,
+            is_char_device: false,
+            size: 0,
+            modified: None,
+            accessed: None,
+            created: None,
+            changed: None,
+            mode: 0,
+            nlink: 0,
+            uid: 0,
+            gid: 0,
+            blocks: 0,
+            inode: 0,
+            rdev: 0,
+            symlink_target: None,
+            extension,
+            git_status: None,
+            xattrs: Vec::new(),
+            attributes: None,
+            content_type: None,
+            child_count: None,
+        }
+    }
+
+    /// Major and minor device numbers, for block and character devices.
+    pub fn device_numbers(&self) -> Option<(u64, u64)> {
+        if !(self.is_block_device || self.is_char_device) {
+            return None;
+        }
+        let dev = self.rdev as libc::dev_t;
+        Some((libc::major(dev) as u64, libc::minor(dev) as u64))
+    }
+
+    /// Read the entry's extended attribute names.
+    pub fn load_xattrs(&mut self) {
+        self.xattrs = xattr::list(&self.path);
+    }
+
+    /// Check a regular file for Linux file capabilities, reading only the
+    /// `security.capability` attribute rather than listing them all.
+    pub fn load_capabilities(&mut self) {
+        let is_file = self.mode & 0o170000 == 0o100000;
+        if is_file
+            && !self.has_capabilities()
+            && xattr::get(&self.path, xattr::CAPABILITY).is_some()
+        {
+            self.xattrs.push(xattr::CAPABILITY.to_string());
+        }
+    }
+
+    /// Read the entry's inode attribute flags (immutable, append-only, ...)
+    /// with the ioctl fallback, if statx did not already report them.
+    pub fn load_attributes(&mut self) {
+        let can_open = self.is_dir || (self.mode & 0o170000 == 0o100000);
+        if self.attributes.is_none() && can_open {
+            self.attributes = FileAttributes::read(&self.path);
+        }
+    }
+
+    /// Detect the type of a regular file from its first bytes.
+    pub fn load_content_type(&mut self) {
+        if self.mode & 0o170000 == 0o100000 {
+            self.content_type = sniff::sniff(&self.path, &self.name);
+        }
+    }
+
+    /// Name used to look up icons and extension colors: the detected type's
+    /// extension when content sniffing found one, otherwise the file name.
+    pub fn icon_name(&self) -> &str {
+        match self.content_type.and_then(|ct| ct.icon_key) {
+            Some(key) => key,
+            None => &self.name,
+        }
+    }
+
+    /// MIME type for the `--mime` column.
+    pub fn mime_type(&self) -> &'static str {
+        if let Some(ct) = self.content_type {
+            return ct.mime;
+        }
+        match self.mode & 0o170000 {
+            0o040000 => "inode/directory",
+            0o120000 => "inode/symlink",
+            0o010000 => "inode/fifo",
+            0o140000 => "inode/socket",
+            0o060000 => "inode/blockdevice",
+            0o020000 => "inode/chardevice",
+            _ => "application/octet-stream",
+        }
+    }
+
+    /// Count the entries of a directory, filtered as a listing of it would be.
+    pub fn load_child_count(&mut self, args: &Args) {
+        if self.is_dir {
+            self.child_count = count_entries(&self.path, args);
+        }
+    }
+
+    /// Whether the entry is marked immutable.
+    pub fn is_immutable(&self) -> bool {
+        self.attributes.is_some_and(|a| a.is_immutable())
+    }
+
+    /// Whether the entry has Linux file capabilities (`security.capability`).
+    pub fn has_capabilities(&self) -> bool {
+        self.xattrs.iter().any(|n| n == xattr::CAPABILITY)
+    }
+
+    /// Whether `--audit` reports the entry: setuid/setgid files, world-writable
+    /// entries other than sticky directories, files with capabilities, and
+    /// group-writable entries owned by someone other than the current user or
+    /// root.
+    pub fn is_risky(&self) -> bool {
+        if self.is_symlink {
+            return false;
+        }
+        let is_file = self.mode & 0o170000 == 0o100000;
+        let sticky_dir = self.is_dir && self.mode & 0o1000 != 0;
+        let setid = is_file && self.mode & 0o6000 != 0;
+        let world_writable = self.mode & 0o002 != 0 && !sticky_dir;
+        let group_writable = self.mode & 0o020 != 0 && !sticky_dir;
+        let foreign = self.uid != 0 && self.uid != current_uid();
+        setid || world_writable || self.has_capabilities() || (foreign && group_writable)
+    }
+
     /// Create synthetic . and .. entries for a directory
     pub fn dot_entries(dir: &Path) -> Vec<FileEntry> {
         let mut entries = Vec::with_capacity(2);
@@ -125,85 +344,183 @@ impl FileEntry {
 
 /// Read directory entries, filtered according to the arguments.
 pub fn read_directory(dir: &Path, args: &Args) -> std::io::Result<Vec<FileEntry>> {
-    let mut entries = Vec::new();
+    Ok(DirEntries::open(dir, args)?.collect())
+}
 
-    // Add . and .. if requested
-    if args.show_dot_dirs() {
-        entries.extend(FileEntry::dot_entries(dir));
-    }
+/// Lazily reads the filtered entries of a directory, building each
+/// `FileEntry` only as readdir returns it. Used directly for streaming output.
+pub struct DirEntries<'a> {
+    dots: std::vec::IntoIter<FileEntry>,
+    rd: fs::ReadDir,
+    args: &'a Args,
+    follow_symlinks: bool,
+    need_stat: bool,
+    /// Colors are on: regular files and directories need their mode
+    mode_for_color: bool,
+    /// Icons are on: regular files without a name icon need their mode
+    mode_for_icons: bool,
+    need_link_target: bool,
+    need_extras: bool,
+    need_attributes: bool,
+    want_caps: bool,
+    sniff: bool,
+    count_children: bool,
+}
 
-    let follow_symlinks = args.dereference;
+impl<'a> DirEntries<'a> {
+    pub fn open(dir: &Path, args: &'a Args) -> std::io::Result<Self> {
+        let rd = fs::read_dir(dir)?;
 
-    let rd = fs::read_dir(dir)?;
-    for entry_result in rd {
-        let entry = match entry_result {
-            Ok(e) => e,
-            Err(_) => continue,
+        // Add . and .. if requested
+        let dots = if args.show_dot_dirs() {
+            FileEntry::dot_entries(dir)
+        } else {
+            Vec::new()
         };
 
-        let name = entry.file_name().to_string_lossy().into_owned();
+        Ok(DirEntries {
+            dots: dots.into_iter(),
+            rd,
+            args,
+            follow_symlinks: args.dereference,
+            need_stat: args.needs_metadata(),
+            mode_for_color: args.use_color(),
+            mode_for_icons: !args.no_icons,
+            need_link_target: args.is_long() || args.one_per_line,
+            need_extras: args.is_long(),
+            need_attributes: args.needs_attributes(),
+            want_caps: args.use_color() || args.audit,
+            sniff: args.sniff_content(),
+            count_children: args.dir_count.is_some(),
+        })
+    }
 
-        // Skip hidden files unless requested
-        if name.starts_with('.') && !args.show_hidden() {
-            continue;
+    /// Load extended attributes for long view (else just the capabilities of
+    /// regular files when colors or --audit check for them), inode flags when
+    /// they are shown or highlighted and statx did not report them, the
+    /// content type of regular files with --sniff or --mime, and the entry
+    /// counts of directories with --dir-count.
+    fn load_extras(&self, fe: &mut FileEntry) {
+        if self.need_extras {
+            fe.load_xattrs();
+        } else if self.want_caps {
+            fe.load_capabilities();
         }
-
-        // Skip backup files if requested
-        if args.ignore_backups && name.ends_with('~') {
-            continue;
+        if self.need_attributes {
+            fe.load_attributes();
+        }
+        if self.sniff {
+            fe.load_content_type();
         }
+        if self.count_children {
+            fe.load_child_count(self.args);
+        }
+    }
+}
 
-        // Skip files matching ignore pattern
-        if let Some(ref pattern) = args.ignore_pattern {
-            if matches_glob_simple(&name, pattern) {
-                continue;
+impl DirEntries<'_> {
+    /// Whether colors or icons depend on an entry's permission bits: the
+    /// executable, setuid and setgid colors of regular files, the sticky and
+    /// other-writable colors of directories, and the executable icon of files
+    /// with no name or extension icon. Anything else is drawn from d_type.
+    fn needs_mode(&self, entry: &fs::DirEntry, name: &str) -> bool {
+        match entry.file_type() {
+            Ok(ft) if ft.is_file() => {
+                self.mode_for_color || (self.mode_for_icons && icons::depends_on_mode(name))
             }
+            Ok(ft) if ft.is_dir() => self.mode_for_color,
+            Ok(_) => false,
+            Err(_) => self.mode_for_color || self.mode_for_icons,
         }
+    }
+}
 
-        // Skip files matching hide pattern (unless -a or -A)
-        if !args.show_hidden() {
-            if let Some(ref pattern) = args.hide_pattern {
-                if matches_glob_simple(&name, pattern) {
-                    continue;
-                }
-            }
+impl Iterator for DirEntries<'_> {
+    type Item = FileEntry;
+
+    fn next(&mut self) -> Option<FileEntry> {
+        if let Some(mut dot) = self.dots.next() {
+            self.load_extras(&mut dot);
+            return Some(dot);
         }
 
-        let path = entry.path();
-        match FileEntry::from_path(&path, follow_symlinks) {
-            Ok(fe) => entries.push(fe),
-            Err(_) => {
-                // If we can't read metadata, create a minimal entry
-                entries.push(FileEntry {
-                    name,
-                    path,
-                    is_dir: false,
-                    is_symlink: false,
-                    is_executable: false,
+        for entry_result in self.rd.by_ref() {
+            let entry = match entry_result {
+                Ok(e) => e,
+                Err(_) => continue,
+            };
+
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if is_filtered(&name, self.args) {
+                continue;
+            }
+
+            let need_stat = self.need_stat || self.needs_mode(&entry, &name);
+            let mut fe = FileEntry::from_dir_entry(
+                &entry,
+                name,
+                self.follow_symlinks,
+                need_stat,
+                self.need_link_target,
+            )
+            .unwrap_or_else(|_| {
+                // If we can't read metadata, create a minimal entry, drawn
+                // like a hidden file with no extension
+                let name = entry.file_name().to_string_lossy().into_owned();
+                FileEntry {
                     is_hidden: true,
-                    is_pipe: false,
-                    is_socket: false,
-                    is_block_device: false,
-                    is_char_device: false,
-                    size: 0,
-                    modified: None,
-                    accessed: None,
-                    created: None,
-                    mode: 0,
-                    nlink: 0,
-                    uid: 0,
-                    gid: 0,
-                    blocks: 0,
-                    inode: 0,
-                    symlink_target: None,
                     extension: String::new(),
-                    git_status: None,
-                });
+                    ..FileEntry::bare(name, entry.path())
+                }
+            });
+            self.load_extras(&mut fe);
+            return Some(fe);
+        }
+
+        None
+    }
+}
+
+/// Count the entries of a directory that a listing of it would show (per
+/// -a/-A, -B, -I and --hide), from readdir alone. `.` and `..` are not counted.
+pub fn count_entries(dir: &Path, args: &Args) -> Option<u64> {
+    let rd = fs::read_dir(dir).ok()?;
+    let count = rd
+        .filter_map(Result::ok)
+        .filter(|e| !is_filtered(&e.file_name().to_string_lossy(), args))
+        .count();
+    Some(count as u64)
+}
+
+/// Whether a name is filtered out by -a/-A, -B, -I and --hide.
+fn is_filtered(name: &str, args: &Args) -> bool {
+    // Skip hidden files unless requested
+    if name.starts_with('.') && !args.show_hidden() {
+        return true;
+    }
+
+    // Skip backup files if requested
+    if args.ignore_backups && name.ends_with('~') {
+        return true;
+    }
+
+    // Skip files matching ignore pattern
+    if let Some(ref pattern) = args.ignore_pattern {
+        if matches_glob_simple(name, pattern) {
+            return true;
+        }
+    }
+
+    // Skip files matching hide pattern (unless -a or -A)
+    if !args.show_hidden() {
+        if let Some(ref pattern) = args.hide_pattern {
+            if matches_glob_simple(name, pattern) {
+                return true;
             }
         }
     }
 
-    Ok(entries)
+    false
 }
 
 /// Apply git status information to a list of file entries.
@@ -221,45 +538,241 @@ pub fn load_git_for_dir(dir: &Path, show_git: bool) -> Option<GitRepo> {
     }
     git::load_git_status(dir)
 }
-pub fn sort_entries(entries: &mut [FileEntry], args: &Args) {
-    if args.no_sort() {
-        return;
+
+/// Per-entry values compared while sorting, computed once up front so the
+/// comparator itself never allocates.
+struct SortKey<'a> {
+    entry: &'a FileEntry,
+    /// Name in a form that compares byte-wise in collation order
+    /// (borrowed when no transformation is needed)
+    collated: Cow<'a, [u8]>,
+    /// Case-folded extension (only filled for extension sort)
+    ext: Cow<'a, str>,
+    /// Display width of the name (only filled for width sort)
+    width: usize,
+    /// Whether names compare in natural order rather than by `collated`
+    natural: bool,
+    /// Owner and group names (only filled for owner/group sort)
+    owner: String,
+    group: String,
+}
+
+impl<'a> SortKey<'a> {
+    fn new(entry: &'a FileEntry, fields: &SortFields, caches: &mut NameCaches) -> Self {
+        SortKey {
+            entry,
+            collated: match fields.collation {
+                Collation::Bytes | Collation::Natural => Cow::Borrowed(entry.name.as_bytes()),
+                Collation::Locale => Cow::Owned(locale::collation_key(&entry.name)),
+                Collation::Folded => match fold_case(&entry.name) {
+                    Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+                    Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+                },
+            },
+            ext: if fields.extension {
+                fold_case(&entry.extension)
+            } else {
+                Cow::Borrowed("")
+            },
+            natural: fields.collation == Collation::Natural,
+            width: if fields.width {
+                unicode_width::UnicodeWidthStr::width(entry.name.as_str())
+            } else {
+                0
+            },
+            owner: if fields.owner {
+                get_username(entry.uid, &mut caches.users)
+            } else {
+                String::new()
+            },
+            group: if fields.group {
+                get_groupname(entry.gid, &mut caches.groups)
+            } else {
+                String::new()
+            },
+        }
     }
+}
 
-    // Determine the primary sort
-    if args.sort_size {
-        entries.sort_by(|a, b| b.size.cmp(&a.size));
-    } else if args.sort_time {
-        entries.sort_by(|a, b| {
-            let ta = a.modified.unwrap_or(SystemTime::UNIX_EPOCH);
-            let tb = b.modified.unwrap_or(SystemTime::UNIX_EPOCH);
-            tb.cmp(&ta)
-        });
-    } else if args.sort_extension {
-        entries.sort_by(|a, b| {
-            let ea = a.extension.to_lowercase();
-            let eb = b.extension.to_lowercase();
-            ea.cmp(&eb)
-                .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
-        });
-    } else if args.version_sort {
-        entries.sort_by(|a, b| version_compare(&a.name, &b.name));
+/// Which of the optional precomputed key parts the active sort keys use.
+struct SortFields {
+    collation: Collation,
+    extension: bool,
+    width: bool,
+    owner: bool,
+    group: bool,
+}
+
+#[derive(Default)]
+struct NameCaches {
+    users: HashMap<u32, String>,
+    groups: HashMap<u32, String>,
+}
+
+/// Lowercase a string, avoiding the allocation when it has no uppercase letters.
+fn fold_case(s: &str) -> Cow<'_, str> {
+    if s.chars().any(char::is_uppercase) {
+        Cow::Owned(s.to_lowercase())
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+/// Rank used by `--sort=type`: directories, then links, regular files and special files.
+fn type_rank(e: &FileEntry) -> u8 {
+    if e.is_dir {
+        0
+    } else if e.is_symlink {
+        1
+    } else if e.is_pipe {
+        3
+    } else if e.is_socket {
+        4
+    } else if e.is_block_device {
+        5
+    } else if e.is_char_device {
+        6
+    } else {
+        2
+    }
+}
+
+/// Rank used by `--sort=git-status`: entries needing attention first.
+fn git_rank(status: Option<GitStatus>) -> u8 {
+    match status {
+        Some(GitStatus::Conflicted) => 0,
+        Some(GitStatus::StagedModified) => 1,
+        Some(GitStatus::Modified) => 2,
+        Some(GitStatus::Staged) => 3,
+        Some(GitStatus::Renamed) => 4,
+        Some(GitStatus::Deleted) => 5,
+        Some(GitStatus::Untracked) => 6,
+        Some(GitStatus::Clean) => 7,
+        Some(GitStatus::Ignored) => 8,
+        None => 9,
+    }
+}
+
+/// Compare two entries by a single field in its natural direction:
+/// sizes and times descending (largest/newest first), everything else ascending.
+fn compare_field(a: &SortKey, b: &SortKey, field: SortField) -> std::cmp::Ordering {
+    let epoch = SystemTime::UNIX_EPOCH;
+    let (ea, eb) = (a.entry, b.entry);
+    match field {
+        SortField::None => std::cmp::Ordering::Equal,
+        SortField::Name => compare_names(a, b),
+        SortField::Size => eb.size.cmp(&ea.size),
+        SortField::Time => eb
+            .modified
+            .unwrap_or(epoch)
+            .cmp(&ea.modified.unwrap_or(epoch)),
+        SortField::Atime => eb
+            .accessed
+            .unwrap_or(epoch)
+            .cmp(&ea.accessed.unwrap_or(epoch)),
+        SortField::Ctime => eb
+            .changed
+            .unwrap_or(epoch)
+            .cmp(&ea.changed.unwrap_or(epoch)),
+        SortField::Birth => eb
+            .created
+            .unwrap_or(epoch)
+            .cmp(&ea.created.unwrap_or(epoch)),
+        SortField::Extension => a.ext.cmp(&b.ext),
+        SortField::Version => {
+            filevercmp(ea.name.as_bytes(), eb.name.as_bytes()).then_with(|| ea.name.cmp(&eb.name))
+        }
+        SortField::Width => a.width.cmp(&b.width),
+        SortField::Inode => ea.inode.cmp(&eb.inode),
+        SortField::Type => type_rank(ea).cmp(&type_rank(eb)),
+        SortField::Owner => a.owner.cmp(&b.owner),
+        SortField::Group => a.group.cmp(&b.group),
+        SortField::GitStatus => git_rank(ea.git_status).cmp(&git_rank(eb.git_status)),
+    }
+}
+
+/// Compare two entries by name in collation order.
+fn compare_names(a: &SortKey, b: &SortKey) -> std::cmp::Ordering {
+    if a.natural {
+        natural_compare(&a.entry.name, &b.entry.name)
     } else {
-        // Default: alphabetical (case-insensitive)
-        entries.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+        a.collated.cmp(&b.collated)
     }
+}
 
-    // Group directories first if requested
-    if args.group_directories_first {
-        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir));
+/// Compare two precomputed keys: directories first (if grouping), then each
+/// sort key in turn, with remaining ties broken by name. -r reverses everything
+/// but the directory grouping.
+fn compare_keys(
+    a: &SortKey,
+    b: &SortKey,
+    specs: &[SortSpec],
+    reverse: bool,
+    group_dirs: bool,
+) -> std::cmp::Ordering {
+    if group_dirs && a.entry.is_dir != b.entry.is_dir {
+        return b.entry.is_dir.cmp(&a.entry.is_dir);
     }
 
-    // Reverse if requested
-    if args.reverse {
-        entries.reverse();
+    let mut ord = std::cmp::Ordering::Equal;
+    for spec in specs {
+        ord = compare_field(a, b, spec.field);
+        if spec.reverse {
+            ord = ord.reverse();
+        }
+        if ord.is_ne() {
+            break;
+        }
+    }
+    let ord = ord
+        .then_with(|| compare_names(a, b))
+        .then_with(|| a.entry.name.cmp(&b.entry.name));
+
+    if reverse {
+        ord.reverse()
+    } else {
+        ord
     }
 }
 
+pub fn sort_entries(entries: &mut Vec<FileEntry>, args: &Args) {
+    if args.no_sort() {
+        return;
+    }
+
+    let specs = args.sort_specs();
+    let uses = |field| specs.iter().any(|s| s.field == field);
+    let fields = SortFields {
+        collation: locale::collation(),
+        extension: uses(SortField::Extension),
+        width: uses(SortField::Width),
+        owner: uses(SortField::Owner),
+        group: uses(SortField::Group),
+    };
+
+    // Sort indices against the precomputed keys, then move entries into place
+    let mut order: Vec<usize> = (0..entries.len()).collect();
+    {
+        let mut caches = NameCaches::default();
+        let keys: Vec<SortKey> = entries
+            .iter()
+            .map(|e| SortKey::new(e, &fields, &mut caches))
+            .collect();
+        order.sort_by(|&a, &b| {
+            compare_keys(
+                &keys[a],
+                &keys[b],
+                &specs,
+                args.reverse,
+                args.group_directories_first,
+            )
+        });
+    }
+
+    let mut slots: Vec<Option<FileEntry>> = entries.drain(..).map(Some).collect();
+    entries.extend(order.into_iter().filter_map(|i| slots[i].take()));
+}
+
 /// Simple glob matching (supports * and ? wildcards)
 fn matches_glob_simple(name: &str, pattern: &str) -> bool {
     let mut name_chars = name.chars().peekable();
@@ -306,67 +819,182 @@ fn matches_glob_simple(name: &str, pattern: &str) -> bool {
     matches_inner(&mut name_chars, &mut pat_chars)
 }
 
-/// Natural/version sort comparison
-fn version_compare(a: &str, b: &str) -> std::cmp::Ordering {
-    let mut ai = a.chars().peekable();
-    let mut bi = b.chars().peekable();
+/// Natural sort comparison (`--collate=natural`): digit runs compare
+/// numerically, everything else case-insensitively.
+fn natural_compare(a: &str, b: &str) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    let (ab, bb) = (a.as_bytes(), b.as_bytes());
+    let (mut i, mut j) = (0, 0);
 
     loop {
-        match (ai.peek(), bi.peek()) {
-            (None, None) => return std::cmp::Ordering::Equal,
-            (None, Some(_)) => return std::cmp::Ordering::Less,
-            (Some(_), None) => return std::cmp::Ordering::Greater,
-            (Some(&ac), Some(&bc)) => {
-                if ac.is_ascii_digit() && bc.is_ascii_digit() {
-                    // Compare numeric segments
-                    let mut an = String::new();
-                    let mut bn = String::new();
-                    while let Some(&c) = ai.peek() {
-                        if c.is_ascii_digit() {
-                            an.push(c);
-                            ai.next();
-                        } else {
-                            break;
-                        }
-                    }
-                    while let Some(&c) = bi.peek() {
-                        if c.is_ascii_digit() {
-                            bn.push(c);
-                            bi.next();
-                        } else {
-                            break;
-                        }
-                    }
-                    let na: u64 = an.parse().unwrap_or(0);
-                    let nb: u64 = bn.parse().unwrap_or(0);
-                    match na.cmp(&nb) {
-                        std::cmp::Ordering::Equal => continue,
-                        other => return other,
+        match (ab.get(i), bb.get(j)) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(ac), Some(bc)) if ac.is_ascii_digit() && bc.is_ascii_digit() => {
+                // Compare numeric segments without parsing: skip leading zeros,
+                // then a longer run is larger, otherwise compare digit by digit
+                let a_end = i + ab[i..].iter().take_while(|c| c.is_ascii_digit()).count();
+                let b_end = j + bb[j..].iter().take_while(|c| c.is_ascii_digit()).count();
+                let a_num = trim_leading_zeros(&ab[i..a_end]);
+                let b_num = trim_leading_zeros(&bb[j..b_end]);
+                match a_num.len().cmp(&b_num.len()).then_with(|| a_num.cmp(b_num)) {
+                    Ordering::Equal => {
+                        i = a_end;
+                        j = b_end;
                     }
-                } else {
-                    let al = ac.to_lowercase().next().unwrap_or(ac);
-                    let bl = bc.to_lowercase().next().unwrap_or(bc);
-                    match al.cmp(&bl) {
-                        std::cmp::Ordering::Equal => {
-                            // If lowercase-equal, compare original case
-                            match ac.cmp(&bc) {
-                                std::cmp::Ordering::Equal => {
-                                    ai.next();
-                                    bi.next();
-                                }
-                                other => {
-                                    ai.next();
-                                    bi.next();
-                                    return other;
-                                }
-                            }
-                        }
-                        other => return other,
+                    other => return other,
+                }
+            }
+            (Some(_), Some(_)) => {
+                // Digit runs are ASCII, so i and j always sit on char boundaries
+                let ac = a[i..].chars().next().unwrap_or_default();
+                let bc = b[j..].chars().next().unwrap_or_default();
+                let al = ac.to_lowercase().next().unwrap_or(ac);
+                let bl = bc.to_lowercase().next().unwrap_or(bc);
+                // If lowercase-equal, compare original case
+                match al.cmp(&bl).then_with(|| ac.cmp(&bc)) {
+                    Ordering::Equal => {
+                        i += ac.len_utf8();
+                        j += bc.len_utf8();
                     }
+                    other => return other,
+                }
+            }
+        }
+    }
+}
+
+fn trim_leading_zeros(digits: &[u8]) -> &[u8] {
+    let zeros = digits.iter().take_while(|&&c| c == b'0').count();
+    &digits[zeros..]
+}
+
+/// Version comparison with the semantics of gnulib's `filevercmp`, as used by
+/// `ls -v`: ".", ".." and other hidden names sort first, file suffixes such as
+/// `.tar.gz` are only compared when the rest is equal, `~` sorts before
+/// everything, and digit runs compare numerically at any length.
+fn filevercmp(a: &[u8], b: &[u8]) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    if a == b {
+        return Ordering::Equal;
+    }
+
+    // Special cases for empty names
+    match (a.is_empty(), b.is_empty()) {
+        (true, _) => return Ordering::Less,
+        (_, true) => return Ordering::Greater,
+        _ => {}
+    }
+
+    // "." sorts first, then "..", then other hidden names, then the rest
+    match (a[0] == b'.', b[0] == b'.') {
+        (true, false) => return Ordering::Less,
+        (false, true) => return Ordering::Greater,
+        (true, true) => {
+            for special in [&b"."[..], &b".."[..]] {
+                match (a == special, b == special) {
+                    (true, _) => return Ordering::Less,
+                    (_, true) => return Ordering::Greater,
+                    _ => {}
                 }
             }
         }
+        (false, false) => {}
+    }
+
+    // Compare without file suffixes first; only if those are equal compare
+    // the full names
+    let a_prefix = file_prefix_len(a);
+    let b_prefix = file_prefix_len(b);
+    let one_pass_only = a_prefix == a.len() && b_prefix == b.len();
+
+    match verrevcmp(&a[..a_prefix], &b[..b_prefix]) {
+        Ordering::Equal if !one_pass_only => verrevcmp(a, b),
+        other => other,
+    }
+}
+
+/// Length of `s` without its file suffix, i.e. the longest trailing run of
+/// `(\.[A-Za-z~][A-Za-z0-9~]*)*`. The first byte is never part of the suffix.
+fn file_prefix_len(s: &[u8]) -> usize {
+    let n = s.len();
+    let mut prefix_len = 0;
+    let mut i = 0;
+
+    while i < n {
+        i += 1;
+        prefix_len = i;
+        while i + 1 < n && s[i] == b'.' && (s[i + 1].is_ascii_alphabetic() || s[i + 1] == b'~') {
+            i += 2;
+            while i < n && (s[i].is_ascii_alphanumeric() || s[i] == b'~') {
+                i += 1;
+            }
+        }
     }
+
+    prefix_len
+}
+
+/// Sort weight of a non-digit byte in `verrevcmp`: `~` first, then the end of
+/// the string and digits, then letters, then everything else.
+fn version_order(c: Option<u8>) -> i32 {
+    match c {
+        None => 0,
+        Some(c) if c.is_ascii_digit() => 0,
+        Some(c) if c.is_ascii_alphabetic() => i32::from(c),
+        Some(b'~') => -1,
+        Some(c) => i32::from(c) + 256,
+    }
+}
+
+/// Debian-style version comparison of alternating non-digit and digit runs.
+fn verrevcmp(a: &[u8], b: &[u8]) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        // Non-digit prefix
+        while (i < a.len() && !a[i].is_ascii_digit()) || (j < b.len() && !b[j].is_ascii_digit()) {
+            let ac = version_order(a.get(i).copied());
+            let bc = version_order(b.get(j).copied());
+            if ac != bc {
+                return ac.cmp(&bc);
+            }
+            i += 1;
+            j += 1;
+        }
+
+        // Digit run, ignoring leading zeros; the longer run is larger, and
+        // otherwise the first differing digit decides
+        while i < a.len() && a[i] == b'0' {
+            i += 1;
+        }
+        while j < b.len() && b[j] == b'0' {
+            j += 1;
+        }
+        let mut first_diff = Ordering::Equal;
+        while i < a.len() && j < b.len() && a[i].is_ascii_digit() && b[j].is_ascii_digit() {
+            if first_diff == Ordering::Equal {
+                first_diff = a[i].cmp(&b[j]);
+            }
+            i += 1;
+            j += 1;
+        }
+        if i < a.len() && a[i].is_ascii_digit() {
+            return Ordering::Greater;
+        }
+        if j < b.len() && b[j].is_ascii_digit() {
+            return Ordering::Less;
+        }
+        if first_diff != Ordering::Equal {
+            return first_diff;
+        }
+    }
+
+    Ordering::Equal
 }
 
 /// Gather entries for a single path argument.
@@ -388,6 +1016,30 @@ pub fn gather_entries(path: &Path, args: &Args) -> std::io::Result<Vec<FileEntry
     }
 }
 
+/// The real user ID of this process.
+pub fn current_uid() -> u32 {
+    static UID: OnceLock<u32> = OnceLock::new();
+    *UID.get_or_init(uzers::get_current_uid)
+}
+
+/// Whether the current user is a member of group `gid`.
+pub fn in_current_groups(gid: u32) -> bool {
+    static GROUPS: OnceLock<Vec<u32>> = OnceLock::new();
+    GROUPS
+        .get_or_init(|| {
+            let mut groups = vec![uzers::get_current_gid()];
+            let n = unsafe { libc::getgroups(0, std::ptr::null_mut()) };
+            if n > 0 {
+                let mut list: Vec<libc::gid_t> = vec![0; n as usize];
+                let n = unsafe { libc::getgroups(n, list.as_mut_ptr()) };
+                list.truncate(n.max(0) as usize);
+                groups.extend(list);
+            }
+            groups
+        })
+        .contains(&gid)
+}
+
 /// Map of user IDs to user names (cached).
 pub fn get_username(uid: u32, cache: &mut HashMap<u32, String>) -> String {
     if let Some(name) = cache.get(&uid) {
@@ -411,3 +1063,296 @@ pub fn get_groupname(gid: u32, cache: &mut HashMap<u32, String>) -> String {
     cache.insert(gid, name.clone());
     name
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cmp::Ordering;
+
+    /// Names in ascending `filevercmp` order, from gnulib's test-filevercmp.c.
+    const ORDERED: &[&str] = &[
+        "",
+        ".",
+        "..",
+        ".0",
+        ".9",
+        ".A",
+        ".Z",
+        ".a~",
+        ".a",
+        ".b~",
+        ".b",
+        ".z",
+        ".zz~",
+        ".zz",
+        ".zz.~1~",
+        ".zz.0",
+        "0",
+        "9",
+        "A",
+        "Z",
+        "a~",
+        "a",
+        "a.b~",
+        "a.b",
+        "a.bc~",
+        "a.bc",
+        "a+",
+        "a.",
+        "a..a",
+        "a.+",
+        "b~",
+        "b",
+        "gcc-c++-10.fc9.tar.gz",
+        "gcc-c++-10.fc9.tar.gz.~1~",
+        "gcc-c++-10.fc9.tar.gz.~2~",
+        "gcc-c++-10.8.12-0.7rc2.fc9.tar.bz2",
+        "gcc-c++-10.8.12-0.7rc2.fc9.tar.bz2.~1~",
+        "glibc-2-0.1.beta1.fc10.rpm",
+        "glibc-common-5-0.2.beta2.fc9.ebuild",
+        "glibc-common-5-0.2b.deb",
+        "glibc-common-11b.ebuild",
+        "glibc-common-11-0.6rc2.ebuild",
+        "libstdc++-0.5.8.11-0.7rc2.fc10.tar.gz",
+        "libstdc++-4a.fc8.tar.gz",
+        "libstdc++-4.10.4.20040204svn.rpm",
+        "libstdc++-devel-3.fc8.ebuild",
+        "libstdc++-devel-3a.fc9.tar.gz",
+        "libstdc++-devel-8.fc8.deb",
+        "libstdc++-devel-8.6.2-0.4b.fc8",
+        "nss_ldap-1-0.2b.fc9.tar.bz2",
+        "nss_ldap-1-0.6rc2.fc8.tar.gz",
+        "nss_ldap-1.0-0.1a.tar.gz",
+        "nss_ldap-10beta1.fc8.tar.gz",
+        "nss_ldap-10.11.8.6.20040204cvs.fc10.ebuild",
+        "z",
+        "zz~",
+        "zz",
+        "zz.~1~",
+        "zz.0",
+        "zz.0.txt",
+        "#.b#",
+    ];
+
+    #[test]
+    fn gnulib_order() {
+        for (i, a) in ORDERED.iter().enumerate() {
+            for (j, b) in ORDERED.iter().enumerate() {
+                assert_eq!(
+                    filevercmp(a.as_bytes(), b.as_bytes()),
+                    i.cmp(&j),
+                    "filevercmp({:?}, {:?})",
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn numeric_runs() {
+        let cases = [
+            ("file-1.9", "file-1.10", Ordering::Less),
+            ("file-2", "file-10", Ordering::Less),
+            ("v1.7", "v1.0007", Ordering::Equal),
+            ("file-01", "file-1", Ordering::Equal),
+            (
+                "a99999999999999999999999",
+                "a100000000000000000000000",
+                Ordering::Less,
+            ),
+            ("a1.tar.gz", "a2.tar", Ordering::Less),
+            ("a1.tar.gz", "a1.tar", Ordering::Greater),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(
+                filevercmp(a.as_bytes(), b.as_bytes()),
+                expected,
+                "filevercmp({:?}, {:?})",
+                a,
+                b
+            );
+        }
+    }
+
+    #[test]
+    fn risky_entries() {
+        let other = current_uid() + 1000;
+        let cases = [
+            (0o100644, 0, false),
+            (0o100644, other, false),
+            (0o100755, other, false),
+            (0o100664, other, true),
+            (0o100664, current_uid(), false),
+            (0o100666, current_uid(), true),
+            (0o104755, 0, true),
+            (0o102755, current_uid(), true),
+            (0o040755, other, false),
+            (0o040775, other, true),
+            (0o040777, current_uid(), true),
+            (0o041777, 0, false),
+            (0o041775, other, false),
+            (0o120777, other, false),
+        ];
+        for (mode, uid, expected) in cases {
+            let mut entry = FileEntry::bare("f".to_string(), PathBuf::from("f"));
+            entry.mode = mode;
+            entry.uid = uid;
+            entry.is_dir = mode & 0o170000 == 0o040000;
+            entry.is_symlink = mode & 0o170000 == 0o120000;
+            assert_eq!(entry.is_risky(), expected, "mode {:o} uid {}", mode, uid);
+        }
+
+        let mut entry = FileEntry::bare("f".to_string(), PathBuf::from("f"));
+        entry.mode = 0o100644;
+        entry.xattrs.push(xattr::CAPABILITY.to_string());
+        assert!(entry.is_risky());
+    }
+
+    #[test]
+    fn needs_mode() {
+        use clap::Parser;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = std::env::temp_dir().join(format!("lsf-test-needs-mode-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        for name in ["main.rs", "Makefile", "script"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        std::os::unix::fs::symlink("script", dir.join("link")).unwrap();
+        let fifo = std::ffi::CString::new(dir.join("fifo").as_os_str().as_bytes()).unwrap();
+        // SAFETY: fifo is a valid NUL-terminated path.
+        assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o644) }, 0);
+
+        // (flags, names that need their mode)
+        let cases: [(&[&str], &[&str]); 4] = [
+            (&["--color=never", "--no-icons"], &[]),
+            (&["--color=never"], &["script"]),
+            (
+                &["--color=always", "--no-icons"],
+                &["Makefile", "main.rs", "script", "src"],
+            ),
+            (
+                &["--color=always"],
+                &["Makefile", "main.rs", "script", "src"],
+            ),
+        ];
+        for (flags, expected) in cases {
+            let args = Args::parse_from(std::iter::once("lsf").chain(flags.iter().copied()));
+            let entries = DirEntries::open(&dir, &args).unwrap();
+            let mut needing: Vec<String> = fs::read_dir(&dir)
+                .unwrap()
+                .map(Result::unwrap)
+                .map(|e| (e.file_name().to_string_lossy().into_owned(), e))
+                .filter(|(name, e)| entries.needs_mode(e, name))
+                .map(|(name, _)| name)
+                .collect();
+            needing.sort();
+            assert_eq!(needing, expected, "{:?}", flags);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn multi_key_sort() {
+        use clap::Parser;
+
+        // (name, is_dir, size)
+        let listing = [
+            ("b.txt", false, 10),
+            ("a.rs", false, 10),
+            ("C.md", false, 30),
+            ("d", true, 0),
+            ("e.rs", false, 5),
+        ];
+        let cases: [(&[&str], &[&str]); 7] = [
+            (&[], &["a.rs", "b.txt", "C.md", "d", "e.rs"]),
+            (&["-S"], &["C.md", "a.rs", "b.txt", "e.rs", "d"]),
+            (&["--sort=-size"], &["d", "e.rs", "a.rs", "b.txt", "C.md"]),
+            (
+                &["--sort=extension,-size"],
+                &["d", "C.md", "e.rs", "a.rs", "b.txt"],
+            ),
+            (
+                &["--sort=size,name", "-r"],
+                &["d", "e.rs", "b.txt", "a.rs", "C.md"],
+            ),
+            (
+                &["-S", "--group-directories-first"],
+                &["d", "C.md", "a.rs", "b.txt", "e.rs"],
+            ),
+            (
+                &["--sort=-size", "-r", "--group-directories-first"],
+                &["d", "C.md", "b.txt", "a.rs", "e.rs"],
+            ),
+        ];
+        for (flags, expected) in cases {
+            let args = Args::parse_from(std::iter::once("lsf").chain(flags.iter().copied()));
+            let mut entries: Vec<FileEntry> = listing
+                .iter()
+                .map(|&(name, is_dir, size)| {
+                    let mut entry = FileEntry::bare(name.to_string(), PathBuf::from(name));
+                    entry.is_dir = is_dir;
+                    entry.size = size;
+                    entry
+                })
+                .collect();
+            sort_entries(&mut entries, &args);
+            let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+            assert_eq!(names, expected, "{:?}", flags);
+        }
+    }
+
+    #[test]
+    fn device_numbers() {
+        let mut entry = FileEntry::bare("sda1".to_string(), PathBuf::from("sda1"));
+        entry.rdev = libc::makedev(8, 1) as u64;
+        assert_eq!(entry.device_numbers(), None);
+        entry.is_block_device = true;
+        assert_eq!(entry.device_numbers(), Some((8, 1)));
+        // Minor numbers above 255 use the extended encoding
+        entry.rdev = libc::makedev(259, 65536) as u64;
+        assert_eq!(entry.device_numbers(), Some((259, 65536)));
+
+        let null = FileEntry::from_path(Path::new("/dev/null"), false).unwrap();
+        assert!(null.is_char_device);
+        assert_eq!(null.device_numbers(), Some((1, 3)));
+        let dir = FileEntry::from_path(Path::new("/dev"), false).unwrap();
+        assert_eq!(dir.device_numbers(), None);
+    }
+
+    #[test]
+    fn counting_entries() {
+        use clap::Parser;
+
+        let dir = std::env::temp_dir().join(format!("lsf-test-count-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        for name in [".hidden", "a.txt", "b.log", "notes~", ".cache.log"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        let cases: [(&[&str], u64); 9] = [
+            (&[], 4),
+            (&["-a"], 6),
+            (&["-A"], 6),
+            (&["-B"], 3),
+            (&["-I", "*.log"], 3),
+            (&["-a", "-I", "*.log"], 4),
+            (&["--hide=*.txt"], 3),
+            // -a and -A override --hide
+            (&["-A", "--hide=*.txt"], 6),
+            (&["-A", "-B", "-I", ".*"], 3),
+        ];
+        for (flags, expected) in cases {
+            let args = Args::parse_from(std::iter::once("lsf").chain(flags.iter().copied()));
+            assert_eq!(count_entries(&dir, &args), Some(expected), "{:?}", flags);
+        }
+        let args = Args::parse_from(["lsf"]);
+        assert_eq!(count_entries(&dir.join("missing"), &args), None);
+        assert_eq!(count_entries(&dir.join("sub"), &args), Some(0));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
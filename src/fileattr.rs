@@ -0,0 +1,118 @@
+use std::path::Path;
+
+/// Inode attribute flags reported by statx (or the FS_IOC_GETFLAGS ioctl on
+/// kernels and filesystems where statx reports none): immutable, append-only, compressed and so on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FileAttributes {
+    flags: u64,
+}
+
+// STATX_ATTR_* values; all but DAX and MOUNT_ROOT match the FS_*_FL flags
+const COMPRESSED: u64 = 0x0004;
+const IMMUTABLE: u64 = 0x0010;
+const APPEND: u64 = 0x0020;
+const NODUMP: u64 = 0x0040;
+const ENCRYPTED: u64 = 0x0800;
+const MOUNT_ROOT: u64 = 0x2000;
+const VERITY: u64 = 0x0010_0000;
+const DAX: u64 = 0x0020_0000;
+
+/// Column letters in display order. Letters follow lsattr where it has one;
+/// `M` marks a mount point root.
+const LETTERS: [(char, u64); 8] = [
+    ('i', IMMUTABLE),
+    ('a', APPEND),
+    ('d', NODUMP),
+    ('c', COMPRESSED),
+    ('E', ENCRYPTED),
+    ('V', VERITY),
+    ('x', DAX),
+    ('M', MOUNT_ROOT),
+];
+
+impl FileAttributes {
+    /// The attributes statx reported, or None if the filesystem reports
+    /// none (an empty attribute mask, as glibc's emulation on old kernels
+    /// also returns).
+    pub fn from_statx(attributes: u64, mask: u64) -> Option<Self> {
+        if mask == 0 {
+            return None;
+        }
+        Some(FileAttributes {
+            flags: attributes & mask,
+        })
+    }
+
+    /// Read the attributes of a file with the FS_IOC_GETFLAGS ioctl, for
+    /// when statx did not report them. This opens the file (without
+    /// following symlinks), so callers only use it for regular files and
+    /// directories; opening devices can have side effects.
+    pub fn read(path: &Path) -> Option<Self> {
+        sys::read(path).map(|flags| FileAttributes { flags })
+    }
+
+    pub fn is_immutable(&self) -> bool {
+        self.flags & IMMUTABLE != 0
+    }
+
+    /// lsattr-style rendering, e.g. `i-d-----`.
+    pub fn to_letters(self) -> String {
+        LETTERS
+            .iter()
+            .map(|&(c, flag)| if self.flags & flag != 0 { c } else { '-' })
+            .collect()
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    /// Inode flags via FS_IOC_GETFLAGS, as lsattr reads them.
+    pub fn read(path: &Path) -> Option<u64> {
+        const FS_DAX_FL: u64 = 0x0200_0000;
+
+        let cpath = CString::new(path.as_os_str().as_bytes()).ok()?;
+
+        let fd = unsafe {
+            libc::open(
+                cpath.as_ptr(),
+                libc::O_RDONLY | libc::O_NONBLOCK | libc::O_NOFOLLOW | libc::O_CLOEXEC,
+            )
+        };
+        if fd < 0 {
+            return None;
+        }
+
+        let mut fs_flags: libc::c_long = 0;
+        let ret = unsafe { libc::ioctl(fd, libc::FS_IOC_GETFLAGS, &mut fs_flags) };
+        unsafe { libc::close(fd) };
+        if ret != 0 {
+            return None;
+        }
+
+        let fs_flags = fs_flags as u64;
+        let mut flags = fs_flags
+            & (super::COMPRESSED
+                | super::IMMUTABLE
+                | super::APPEND
+                | super::NODUMP
+                | super::ENCRYPTED
+                | super::VERITY);
+        if fs_flags & FS_DAX_FL != 0 {
+            flags |= super::DAX;
+        }
+        Some(flags)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use std::path::Path;
+
+    pub fn read(_path: &Path) -> Option<u64> {
+        None
+    }
+}
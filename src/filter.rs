@@ -0,0 +1,425 @@
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+use crate::cli::Args;
+use crate::entry::FileEntry;
+
+/// A `--size=BOUND` bound: `+N` (larger than), `-N` (smaller than) or `N`
+/// (exactly). As in find(1), the size is first rounded up to N's unit, so
+/// `-1k` matches only empty files and `+1M` needs at least 1M plus one byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeBound {
+    cmp: std::cmp::Ordering,
+    count: u64,
+    unit: u64,
+}
+
+impl SizeBound {
+    fn matches(self, size: u64) -> bool {
+        size.div_ceil(self.unit).cmp(&self.count) == self.cmp
+    }
+}
+
+pub fn parse_size(s: &str) -> Result<SizeBound, String> {
+    let (cmp, rest) = match s.as_bytes().first() {
+        Some(b'+') => (std::cmp::Ordering::Greater, &s[1..]),
+        Some(b'-') => (std::cmp::Ordering::Less, &s[1..]),
+        _ => (std::cmp::Ordering::Equal, s),
+    };
+    let digits = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let unit = match &rest[digits..] {
+        "" | "c" => 1,
+        "k" | "K" => 1 << 10,
+        "m" | "M" => 1 << 20,
+        "g" | "G" => 1 << 30,
+        "t" | "T" => 1 << 40,
+        _ => return Err(format!("invalid size '{}'", s)),
+    };
+    match rest[..digits].parse::<u64>() {
+        Ok(count) if count.checked_mul(unit).is_some() => Ok(SizeBound { cmp, count, unit }),
+        _ => Err(format!("invalid size '{}'", s)),
+    }
+}
+
+/// Parse a duration like `90s`, `15m`, `3h`, `2d`, `1w` or `1d12h`.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid duration '{}'", s);
+    let mut secs: u64 = 0;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let count: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = &rest[digits..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let unit = match &rest[..unit_len] {
+            "s" => 1,
+            "m" | "min" => 60,
+            "h" => 3600,
+            "d" => 86400,
+            "w" => 7 * 86400,
+            _ => return Err(invalid()),
+        };
+        rest = &rest[unit_len..];
+        secs = count
+            .checked_mul(unit)
+            .and_then(|n| secs.checked_add(n))
+            .ok_or_else(invalid)?;
+    }
+    if s.is_empty() {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// A `--perm` test, as in find(1): `MODE` (exactly these permission bits),
+/// `-MODE` (all of them set) or `/MODE` (any of them set).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermMatch {
+    Exact(u32),
+    All(u32),
+    Any(u32),
+}
+
+impl PermMatch {
+    fn matches(self, mode: u32) -> bool {
+        let mode = mode & 0o7777;
+        match self {
+            PermMatch::Exact(bits) => mode == bits,
+            PermMatch::All(bits) => mode & bits == bits,
+            PermMatch::Any(bits) => bits == 0 || mode & bits != 0,
+        }
+    }
+}
+
+pub fn parse_perm(s: &str) -> Result<PermMatch, String> {
+    let (kind, digits): (fn(u32) -> PermMatch, &str) = match s.as_bytes().first() {
+        Some(b'-') => (PermMatch::All, &s[1..]),
+        Some(b'/') => (PermMatch::Any, &s[1..]),
+        _ => (PermMatch::Exact, s),
+    };
+    let octal = !digits.is_empty() && digits.bytes().all(|c| (b'0'..=b'7').contains(&c));
+    match u32::from_str_radix(digits, 8) {
+        Ok(bits) if octal && bits <= 0o7777 => Ok(kind(bits)),
+        _ => Err(format!("invalid mode '{}' (expected octal, e.g. 644)", s)),
+    }
+}
+
+/// The filters from `--only-dirs`, `--only-files`, `--type`, `--size=BOUND`,
+/// `--newer`, `--changed-within`, `--owner` and `--perm`, with file and
+/// user names resolved. An entry is shown only if it passes all of them.
+#[derive(Debug, Default)]
+struct Filter {
+    /// Type letters as in find(1): f, d, l, p, s, b, c
+    types: Vec<char>,
+    size: Option<SizeBound>,
+    /// Modified after this time (--newer FILE)
+    newer: Option<SystemTime>,
+    /// Status changed (ctime) at or after this time (--changed-within)
+    since: Option<SystemTime>,
+    owner: Option<u32>,
+    perm: Option<PermMatch>,
+}
+
+impl Filter {
+    fn is_empty(&self) -> bool {
+        self.types.is_empty()
+            && self.size.is_none()
+            && self.newer.is_none()
+            && self.since.is_none()
+            && self.owner.is_none()
+            && self.perm.is_none()
+    }
+
+    fn matches(&self, entry: &FileEntry) -> bool {
+        if !self.types.is_empty() && !self.types.contains(&type_letter(entry)) {
+            return false;
+        }
+        if let Some(size) = self.size {
+            if !size.matches(entry.size) {
+                return false;
+            }
+        }
+        if let Some(newer) = self.newer {
+            if !entry.modified.is_some_and(|t| t > newer) {
+                return false;
+            }
+        }
+        if let Some(since) = self.since {
+            if !entry.changed.is_some_and(|t| t >= since) {
+                return false;
+            }
+        }
+        if let Some(uid) = self.owner {
+            if entry.uid != uid {
+                return false;
+            }
+        }
+        if let Some(perm) = self.perm {
+            if !perm.matches(entry.mode) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// The find(1) type letter of an entry.
+fn type_letter(entry: &FileEntry) -> char {
+    if entry.is_symlink {
+        'l'
+    } else if entry.is_dir {
+        'd'
+    } else if entry.is_pipe {
+        'p'
+    } else if entry.is_socket {
+        's'
+    } else if entry.is_block_device {
+        'b'
+    } else if entry.is_char_device {
+        'c'
+    } else {
+        'f'
+    }
+}
+
+static ACTIVE: OnceLock<Filter> = OnceLock::new();
+
+/// Build the filters from the command line, looking up the `--newer`
+/// reference file and the `--owner` user.
+pub fn init(args: &Args) -> Result<(), String> {
+    let mut filter = Filter::default();
+    if args.only_dirs {
+        filter.types.push('d');
+    }
+    if args.only_files {
+        filter.types.push('f');
+    }
+    if let Some(ref types) = args.file_type {
+        filter
+            .types
+            .extend(types.iter().filter_map(|t| t.chars().next()));
+    }
+    filter.size = args.size_filter();
+
+    if let Some(ref path) = args.newer {
+        let modified = std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .map_err(|e| format!("--newer: cannot access '{}': {}", path, e))?;
+        filter.newer = Some(modified);
+    }
+    if let Some(within) = args.changed_within {
+        filter.since = SystemTime::now().checked_sub(within);
+    }
+
+    if let Some(ref owner) = args.owner {
+        let uid = match owner.parse::<u32>() {
+            Ok(uid) => uid,
+            Err(_) => uzers::get_user_by_name(owner)
+                .map(|u| u.uid())
+                .ok_or_else(|| format!("--owner: no such user '{}'", owner))?,
+        };
+        filter.owner = Some(uid);
+    }
+    filter.perm = args.perm;
+
+    let _ = ACTIVE.set(filter);
+    Ok(())
+}
+
+/// Whether any filter is in effect.
+pub fn is_active() -> bool {
+    ACTIVE.get().is_some_and(|f| !f.is_empty())
+}
+
+/// Whether an entry passes the filters (always true when none are set).
+pub fn matches(entry: &FileEntry) -> bool {
+    ACTIVE.get().map_or(true, |f| f.matches(entry))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn size_parsing() {
+        let ok = [
+            ("0", Ordering::Equal, 0, 1),
+            ("+0", Ordering::Greater, 0, 1),
+            ("-1", Ordering::Less, 1, 1),
+            ("100c", Ordering::Equal, 100, 1),
+            ("+10k", Ordering::Greater, 10, 1 << 10),
+            ("-2K", Ordering::Less, 2, 1 << 10),
+            ("5M", Ordering::Equal, 5, 1 << 20),
+            ("+1g", Ordering::Greater, 1, 1 << 30),
+            ("16777215T", Ordering::Equal, 16777215, 1 << 40),
+        ];
+        for (s, cmp, count, unit) in ok {
+            assert_eq!(
+                parse_size(s),
+                Ok(SizeBound { cmp, count, unit }),
+                "parse_size({:?})",
+                s
+            );
+        }
+
+        let bad = [
+            "",
+            "+",
+            "-",
+            "k",
+            "10x",
+            "10kk",
+            "1.5M",
+            "+-1",
+            " 1",
+            "16777216T",
+            "99999999999999999999",
+        ];
+        for s in bad {
+            assert_eq!(parse_size(s), Err(format!("invalid size '{}'", s)));
+        }
+    }
+
+    #[test]
+    fn size_matching() {
+        // Sizes are rounded up to the bound's unit before comparing, in all
+        // three forms
+        let cases = [
+            ("+0", 0, false),
+            ("+0", 1, true),
+            ("0", 0, true),
+            ("0", 1, false),
+            ("-1", 0, true),
+            ("-1", 1, false),
+            ("-1k", 0, true),
+            ("-1k", 1, false),
+            ("-1k", 1023, false),
+            ("-2k", 1024, true),
+            ("-2k", 1025, false),
+            ("+1k", 1024, false),
+            ("+1k", 1025, true),
+            ("+1M", 1 << 20, false),
+            ("+1M", (1 << 20) + 1, true),
+            ("1k", 1, true),
+            ("1k", 1024, true),
+            ("1k", 1025, false),
+            ("2k", 1025, true),
+            ("100c", 100, true),
+            ("+16777215T", u64::MAX, true),
+        ];
+        for (s, size, expected) in cases {
+            assert_eq!(
+                parse_size(s).unwrap().matches(size),
+                expected,
+                "{} against {}",
+                s,
+                size
+            );
+        }
+    }
+
+    #[test]
+    fn duration_parsing() {
+        let ok = [
+            ("0s", 0),
+            ("90s", 90),
+            ("15m", 15 * 60),
+            ("15min", 15 * 60),
+            ("3h", 3 * 3600),
+            ("2d", 2 * 86400),
+            ("1w", 7 * 86400),
+            ("1d12h", 86400 + 12 * 3600),
+            ("1h30m15s", 3600 + 30 * 60 + 15),
+        ];
+        for (s, secs) in ok {
+            assert_eq!(
+                parse_duration(s),
+                Ok(Duration::from_secs(secs)),
+                "parse_duration({:?})",
+                s
+            );
+        }
+
+        let bad = [
+            "",
+            "10",
+            "h",
+            "1x",
+            "1d12",
+            "1 h",
+            "-1h",
+            "99999999999999999999s",
+            "40000000000000w",
+            "18446744073709551615s1s",
+        ];
+        for s in bad {
+            assert_eq!(parse_duration(s), Err(format!("invalid duration '{}'", s)));
+        }
+    }
+
+    #[test]
+    fn perm_parsing() {
+        let ok = [
+            ("644", PermMatch::Exact(0o644)),
+            ("0", PermMatch::Exact(0)),
+            ("7777", PermMatch::Exact(0o7777)),
+            ("-220", PermMatch::All(0o220)),
+            ("/111", PermMatch::Any(0o111)),
+        ];
+        for (s, expected) in ok {
+            assert_eq!(parse_perm(s), Ok(expected), "parse_perm({:?})", s);
+        }
+
+        let bad = [
+            "",
+            "-",
+            "/",
+            "8",
+            "648",
+            "10000",
+            "+644",
+            "/+7",
+            "-/644",
+            "u+x",
+            "99999999999",
+        ];
+        for s in bad {
+            assert_eq!(
+                parse_perm(s),
+                Err(format!("invalid mode '{}' (expected octal, e.g. 644)", s))
+            );
+        }
+    }
+
+    #[test]
+    fn perm_matching() {
+        let cases = [
+            ("644", 0o100644, true),
+            ("644", 0o100755, false),
+            ("4755", 0o104755, true),
+            ("755", 0o104755, false),
+            ("-220", 0o664, true),
+            ("-220", 0o644, false),
+            ("-0", 0o000, true),
+            ("/111", 0o644, false),
+            ("/111", 0o744, true),
+            ("/0", 0o000, true),
+        ];
+        for (s, mode, expected) in cases {
+            assert_eq!(
+                parse_perm(s).unwrap().matches(mode),
+                expected,
+                "{} against {:o}",
+                s,
+                mode
+            );
+        }
+    }
+}
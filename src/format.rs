@@ -1,9 +1,10 @@
 use std::collections::HashMap;
 use std::time::SystemTime;
 
-use chrono::{DateTime, Local};
-
+use crate::cli::{Args, Column};
 use crate::entry::FileEntry;
+use crate::locale;
+use crate::theme::{self, Role};
 
 /// Format the permission mode as an `ls -l` style string (e.g., drwxr-xr-x).
 pub fn format_permissions(mode: u32, is_dir: bool, is_symlink: bool) -> String {
@@ -74,6 +75,12 @@ pub fn format_permissions(mode: u32, is_dir: bool, is_symlink: bool) -> String {
     s
 }
 
+/// Format the permission bits in octal, including setuid/setgid/sticky
+/// (e.g., 0755, 4755), as accepted by chmod.
+pub fn format_octal_permissions(mode: u32) -> String {
+    format!("{:04o}", mode & 0o7777)
+}
+
 /// Format a file size for display.
 pub fn format_size(size: u64, human: bool, si: bool) -> String {
     if !human {
@@ -112,200 +119,831 @@ pub fn format_size(size: u64, human: bool, si: bool) -> String {
     }
 }
 
-/// Format a timestamp for display (like ls).
-pub fn format_time(time: Option<SystemTime>) -> String {
-    let time = match time {
-        Some(t) => t,
-        None => return "            ".to_string(), // 12 chars
-    };
-
-    let datetime: DateTime<Local> = time.into();
-    let now = Local::now();
-    let six_months_ago = now - chrono::Duration::days(182);
+/// The long-view size of a non-device entry: its entry count for directories
+/// when `count_dirs` is set and the count could be read, else its size.
+pub fn size_text(entry: &FileEntry, human: bool, si: bool, count_dirs: bool) -> String {
+    match entry.child_count {
+        Some(count) if count_dirs => count.to_string(),
+        _ => format_size(entry.size, human, si),
+    }
+}
 
-    if datetime < six_months_ago || datetime > now + chrono::Duration::hours(1) {
-        // Show year for old/future files
-        datetime.format("%b %e  %Y").to_string()
-    } else {
-        // Show time for recent files
-        datetime.format("%b %e %H:%M").to_string()
+/// The ` (N)` entry count appended to directory names with `--dir-count=name`.
+pub fn count_suffix(entry: &FileEntry, use_color: bool) -> String {
+    match entry.child_count {
+        Some(count) => {
+            let text = format!("({})", count);
+            if use_color {
+                format!(" {}", theme::paint(Role::DirCount, &text))
+            } else {
+                format!(" {}", text)
+            }
+        }
+        None => String::new(),
     }
 }
 
-/// Format a long-listing line for a single entry.
-#[allow(clippy::too_many_arguments)]
-pub fn format_long_entry(
-    entry: &FileEntry,
-    icon_str: &str,
-    show_icons: bool,
-    show_owner: bool,
-    show_group: bool,
-    show_inode: bool,
-    show_blocks: bool,
-    show_author: bool,
-    human_readable: bool,
-    si: bool,
-    numeric_ids: bool,
-    use_color: bool,
-    user_cache: &mut HashMap<u32, String>,
-    group_cache: &mut HashMap<u32, String>,
-    // Width hints for alignment
-    nlink_width: usize,
-    owner_width: usize,
-    group_width: usize,
-    size_width: usize,
-    inode_width: usize,
-    blocks_width: usize,
-) -> String {
-    let mut line = String::with_capacity(128);
+/// Format a device's numbers as `major, minor`, each right-aligned.
+pub fn format_device(major: u64, minor: u64, major_width: usize, minor_width: usize) -> String {
+    format!(
+        "{:>mw$}, {:>nw$}",
+        major,
+        minor,
+        mw = major_width,
+        nw = minor_width
+    )
+}
+
+/// Files older than this (or in the future) show the year instead of the
+/// time of day. Same as GNU ls: half of an average Gregorian year.
+const SIX_MONTHS_SECS: i64 = 31_556_952 / 2;
+
+/// Timestamp formats for the long view, chosen by `--time-style` (or the
+/// TIME_STYLE environment variable) and the LC_TIME locale.
+pub struct TimeFormat {
+    /// `--time-style=relative`: "3 minutes ago" instead of a date
+    relative: bool,
+    /// strftime format for timestamps within the last six months
+    recent: String,
+    /// strftime format for older and future timestamps
+    old: String,
+    /// Locale month names padded to equal width, substituted for `%b`
+    months: Vec<String>,
+    /// Blank placeholder for missing timestamps
+    blank: String,
+}
+
+impl TimeFormat {
+    pub fn from_args(args: &Args) -> Self {
+        let is_c_locale = locale::time_is_c();
+        let style = args
+            .time_style
+            .clone()
+            .or_else(|| std::env::var("TIME_STYLE").ok())
+            .unwrap_or_default();
+
+        // "posix-STYLE" only applies STYLE outside the POSIX locale
+        let style = match style.strip_prefix("posix-") {
+            Some(_) if is_c_locale => String::new(),
+            Some(rest) => rest.to_string(),
+            None => style,
+        };
 
-    // Inode
-    if show_inode {
-        line.push_str(&format!("{:>width$} ", entry.inode, width = inode_width));
+        if style == "relative" {
+            return TimeFormat {
+                relative: true,
+                recent: String::new(),
+                old: String::new(),
+                months: Vec::new(),
+                blank: " ".repeat(RELATIVE_WIDTH),
+            };
+        }
+
+        // The default (locale) style: month name, day and year or time of day,
+        // with the day first if that is the LC_TIME locale's date order
+        let (default_old, default_recent) = if !is_c_locale && locale::day_before_month() {
+            ("%e %b  %Y".to_string(), "%e %b %H:%M".to_string())
+        } else {
+            ("%b %e  %Y".to_string(), "%b %e %H:%M".to_string())
+        };
+        let (old, recent) = match style.as_str() {
+            "full-iso" => (
+                "%Y-%m-%d %H:%M:%S.%N %z".to_string(),
+                "%Y-%m-%d %H:%M:%S.%N %z".to_string(),
+            ),
+            "long-iso" => ("%Y-%m-%d %H:%M".to_string(), "%Y-%m-%d %H:%M".to_string()),
+            "iso" => ("%Y-%m-%d ".to_string(), "%m-%d %H:%M".to_string()),
+            custom if custom.starts_with('+') => {
+                // "+FORMAT1\nFORMAT2": FORMAT1 for old files, FORMAT2 for recent ones
+                let custom = &custom[1..];
+                match custom.split_once('\n') {
+                    Some((old, recent)) => (old.to_string(), recent.to_string()),
+                    None => (custom.to_string(), custom.to_string()),
+                }
+            }
+            _ => (default_old, default_recent),
+        };
+
+        let months = if old.contains("%b") || recent.contains("%b") {
+            locale::padded_months()
+        } else {
+            Vec::new()
+        };
+
+        let mut fmt = TimeFormat {
+            relative: false,
+            recent,
+            old,
+            months,
+            blank: String::new(),
+        };
+        let sample = fmt.format(Some(SystemTime::now()));
+        fmt.blank = " ".repeat(crate::grid::display_width(&sample));
+        fmt
     }
 
-    // Blocks
-    if show_blocks {
-        let blocks = entry.blocks / 2; // Convert 512-byte blocks to 1K blocks
-        line.push_str(&format!("{:>width$} ", blocks, width = blocks_width));
+    /// Display width of a formatted timestamp.
+    pub fn width(&self) -> usize {
+        self.blank.len()
     }
 
-    // Icon
-    if show_icons {
-        line.push_str(icon_str);
-        line.push(' ');
+    /// Format a timestamp for display (like ls).
+    pub fn format(&self, time: Option<SystemTime>) -> String {
+        let time = match time {
+            Some(t) => t,
+            None => return self.blank.clone(),
+        };
+
+        let (secs, nanos) = match time.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(d) => (d.as_secs() as i64, d.subsec_nanos()),
+            Err(e) => (-(e.duration().as_secs() as i64), 0),
+        };
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        if self.relative {
+            return format!(
+                "{:<width$}",
+                format_relative(now - secs),
+                width = RELATIVE_WIDTH
+            );
+        }
+
+        // Show year for old/future files, time of day for recent ones
+        let recent = secs > now - SIX_MONTHS_SECS && secs <= now;
+        let fmt = if recent { &self.recent } else { &self.old };
+
+        let tm = locale::localtime(secs);
+        let month = self.months.get(tm.tm_mon as usize).map(String::as_str);
+        locale::strftime(&expand_format(fmt, nanos, month), &tm)
+    }
+}
+
+/// Substitute the conversions strftime does not know or lsf pads itself:
+/// `%N` with nanoseconds and, if given, `%b` with a padded month name.
+/// `%%` is kept as is, so `%%N` still prints a literal `%N`.
+fn expand_format(fmt: &str, nanos: u32, month: Option<&str>) -> String {
+    let mut out = String::with_capacity(fmt.len() + 16);
+    let mut chars = fmt.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match (chars.next(), month) {
+            (Some('N'), _) => out.push_str(&format!("{:09}", nanos)),
+            (Some('b'), Some(month)) => out.push_str(&month.replace('%', "%%")),
+            (Some(next), _) => {
+                out.push('%');
+                out.push(next);
+            }
+            (None, _) => out.push('%'),
+        }
     }
+    out
+}
 
-    // Permissions
-    let perms = format_permissions(entry.mode, entry.is_dir, entry.is_symlink);
-    if use_color {
-        line.push_str(&colorize_permissions(&perms));
+/// Width relative timestamps are padded to, as in "59 minutes ago".
+const RELATIVE_WIDTH: usize = 14;
+
+/// Describe an age in seconds (negative for the future) in the largest
+/// whole unit, e.g. "3 minutes ago", "2 weeks ago" or "in 5 hours".
+fn format_relative(age: i64) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+    const WEEK: u64 = 7 * DAY;
+    const MONTH: u64 = 30 * DAY;
+    const YEAR: u64 = 365 * DAY;
+
+    let secs = age.unsigned_abs();
+    if secs == 0 {
+        return "just now".to_string();
+    }
+    let (n, unit) = if secs < MINUTE {
+        (secs, "second")
+    } else if secs < HOUR {
+        (secs / MINUTE, "minute")
+    } else if secs < DAY {
+        (secs / HOUR, "hour")
+    } else if secs < WEEK {
+        (secs / DAY, "day")
+    } else if secs < MONTH {
+        (secs / WEEK, "week")
+    } else if secs < YEAR {
+        (secs / MONTH, "month")
     } else {
-        line.push_str(&perms);
+        (secs / YEAR, "year")
+    };
+    let plural = if n == 1 { "" } else { "s" };
+    if age < 0 {
+        format!("in {} {}{}", n, unit, plural)
+    } else {
+        format!("{} {}{} ago", n, unit, plural)
     }
+}
 
-    // Hard link count
-    line.push_str(&format!(" {:>width$}", entry.nlink, width = nlink_width));
+/// Gradient endpoints for `--color-scale`: old files fade to gray, recent
+/// ones are bright green; small files are gray and large ones orange.
+const AGE_COLD: (u8, u8, u8) = (0x6c, 0x6c, 0x6c);
+const AGE_HOT: (u8, u8, u8) = (0x5f, 0xff, 0x87);
+const SIZE_COLD: (u8, u8, u8) = (0x6c, 0x6c, 0x6c);
+const SIZE_HOT: (u8, u8, u8) = (0xff, 0x87, 0x00);
 
-    // Owner
-    if show_owner {
-        let owner = if numeric_ids {
-            entry.uid.to_string()
+/// Value ranges for `--color-scale`, in log space so that both a minute and a
+/// year, or a kilobyte and a gigabyte, are visibly apart.
+pub struct HeatScale {
+    /// ln(age in seconds) of the most and least recent timestamps
+    age: Option<(f64, f64)>,
+    /// ln(size in bytes) of the smallest and largest regular files
+    size: Option<(f64, f64)>,
+}
+
+impl HeatScale {
+    /// The scale selected by `--color-scale` and `--color-scale-mode`: fixed
+    /// ranges (a minute to a year, 1 KiB to 1 GiB) or the range of `entries`.
+    pub fn from_args(entries: &[FileEntry], args: &Args) -> Option<Self> {
+        let field = args.color_scale.as_deref()?;
+        let relative = args.color_scale_mode == "relative";
+        let now = SystemTime::now();
+
+        let age = if field == "size" {
+            None
+        } else if relative {
+            log_range(entries.iter().filter_map(|e| age_secs(e.modified?, now)))
         } else {
-            crate::entry::get_username(entry.uid, user_cache)
+            Some((60f64.ln(), (365.0 * 86400f64).ln()))
         };
-        line.push_str(&format!(" {:<width$}", owner, width = owner_width));
+        let size = if field == "age" {
+            None
+        } else if relative {
+            log_range(
+                entries
+                    .iter()
+                    .filter(|e| e.mode & 0o170000 == 0o100000)
+                    .map(|e| e.size),
+            )
+        } else {
+            Some((1024f64.ln(), ((1u64 << 30) as f64).ln()))
+        };
+
+        Some(HeatScale { age, size })
     }
 
-    // Group
-    if show_group {
-        let group = if numeric_ids {
-            entry.gid.to_string()
+    /// How hot (0.0 to 1.0) a timestamp is: 1.0 for the most recent.
+    fn age_heat(&self, time: SystemTime) -> Option<f64> {
+        let (lo, hi) = self.age?;
+        let age = age_secs(time, SystemTime::now())?;
+        Some(1.0 - scale(age, lo, hi))
+    }
+
+    /// How hot (0.0 to 1.0) a file size is: 1.0 for the largest.
+    fn size_heat(&self, size: u64) -> Option<f64> {
+        let (lo, hi) = self.size?;
+        Some(scale(size, lo, hi))
+    }
+}
+
+/// Seconds between `time` and `now`, or None for future timestamps.
+fn age_secs(time: SystemTime, now: SystemTime) -> Option<u64> {
+    now.duration_since(time).ok().map(|d| d.as_secs())
+}
+
+/// The range of ln(value) over `values`.
+fn log_range(values: impl Iterator<Item = u64>) -> Option<(f64, f64)> {
+    values
+        .map(|v| (v.max(1) as f64).ln())
+        .fold(None, |range, x| match range {
+            None => Some((x, x)),
+            Some((lo, hi)) => Some((f64::min(lo, x), f64::max(hi, x))),
+        })
+}
+
+/// Where `value` falls between ln-space `lo` and `hi`, from 0.0 to 1.0.
+/// A listing where every value is the same sits in the middle.
+fn scale(value: u64, lo: f64, hi: f64) -> f64 {
+    if hi <= lo {
+        return 0.5;
+    }
+    (((value.max(1) as f64).ln() - lo) / (hi - lo)).clamp(0.0, 1.0)
+}
+
+/// Display options and column widths for long-format lines, measured over a
+/// whole listing so the columns line up.
+pub struct LongFormat {
+    /// Columns in display order
+    pub columns: Vec<Column>,
+    /// `--permissions`: symbolic, octal or both
+    pub perm_style: String,
+    /// Whether some entry has an ACL/xattr marker after its permissions
+    pub show_attr_indicator: bool,
+    pub human_readable: bool,
+    pub si: bool,
+    pub numeric_ids: bool,
+    pub classify: bool,
+    pub slash_dirs: bool,
+    pub use_color: bool,
+    pub time_format: TimeFormat,
+    /// `--dir-count`: directories show their entry count as their size
+    pub count_dirs: bool,
+    /// `--color-scale` gradient ranges
+    pub heat: Option<HeatScale>,
+    pub widths: ColumnWidths,
+}
+
+/// Widths of the padded long-view columns.
+#[derive(Debug, Default)]
+pub struct ColumnWidths {
+    pub inode: usize,
+    pub blocks: usize,
+    pub nlink: usize,
+    pub owner: usize,
+    pub group: usize,
+    pub context: usize,
+    pub size: usize,
+    pub major: usize,
+    pub minor: usize,
+    pub caps: usize,
+    pub mime: usize,
+    pub git: usize,
+    /// Permission and timestamp cells are padded to these widths when the
+    /// header labels are wider than the values (0 means no padding)
+    pub perms: usize,
+    pub time: usize,
+}
+
+impl ColumnWidths {
+    /// Widen the columns in `columns` to fit their header labels.
+    pub fn fit_header(&mut self, columns: &[Column], perm_style: &str, time_format: &TimeFormat) {
+        for &column in columns {
+            let label = header_label(column).len();
+            let width = match column {
+                Column::Inode => &mut self.inode,
+                Column::Blocks => &mut self.blocks,
+                Column::Links => &mut self.nlink,
+                Column::User | Column::Author => &mut self.owner,
+                Column::Group => &mut self.group,
+                Column::Context => &mut self.context,
+                Column::Size => &mut self.size,
+                Column::Caps => &mut self.caps,
+                Column::Mime => &mut self.mime,
+                Column::Git => &mut self.git,
+                Column::Perms => {
+                    self.perms = match perm_style {
+                        "octal" => 4,
+                        "both" => 15,
+                        _ => 10,
+                    };
+                    &mut self.perms
+                }
+                Column::Mtime | Column::Atime | Column::Ctime | Column::Birth => {
+                    self.time = self.time.max(time_format.width());
+                    &mut self.time
+                }
+                _ => continue,
+            };
+            *width = (*width).max(label);
+        }
+    }
+}
+
+/// The header label of a long-view column for `--header`.
+pub fn header_label(column: Column) -> &'static str {
+    match column {
+        Column::Inode => "Inode",
+        Column::Blocks => "Blocks",
+        Column::Icon => "",
+        Column::Perms => "Permissions",
+        Column::Attributes => "Flags",
+        Column::Links => "Links",
+        Column::User => "User",
+        Column::Group => "Group",
+        Column::Author => "Author",
+        Column::Context => "Context",
+        Column::Size => "Size",
+        Column::Mtime => "Modified",
+        Column::Atime => "Accessed",
+        Column::Ctime => "Changed",
+        Column::Birth => "Created",
+        Column::Caps => "Caps",
+        Column::Mime => "MIME type",
+        Column::Git => "Git",
+        Column::Name => "Name",
+    }
+}
+
+/// Format the `--header` line for the columns in `fmt`, each label aligned
+/// like its column's values. `icon_width` is the width of the icon column.
+pub fn format_long_header(fmt: &LongFormat, icon_width: usize) -> String {
+    let widths = &fmt.widths;
+    let mut cells: Vec<String> = Vec::with_capacity(fmt.columns.len());
+
+    for &column in &fmt.columns {
+        let label = header_label(column);
+        let (width, right_align) = match column {
+            Column::Inode => (widths.inode, true),
+            Column::Blocks => (widths.blocks, true),
+            Column::Links => (widths.nlink, true),
+            Column::Size => (widths.size, true),
+            Column::Icon => (icon_width, false),
+            Column::Perms => (widths.perms, false),
+            Column::Attributes => (8, false),
+            Column::User | Column::Author => (widths.owner, false),
+            Column::Group => (widths.group, false),
+            Column::Context => (widths.context, false),
+            Column::Mtime | Column::Atime | Column::Ctime | Column::Birth => (widths.time, false),
+            Column::Caps => (widths.caps, false),
+            Column::Mime => (widths.mime, false),
+            Column::Git => (widths.git, false),
+            Column::Name => (0, false),
+        };
+
+        let pad = " ".repeat(width.saturating_sub(label.len()));
+        let label = if fmt.use_color && !label.is_empty() {
+            theme::paint(Role::ColumnHeader, label)
         } else {
-            crate::entry::get_groupname(entry.gid, group_cache)
+            label.to_string()
         };
-        line.push_str(&format!(" {:<width$}", group, width = group_width));
+        cells.push(if right_align {
+            format!("{}{}", pad, label)
+        } else {
+            format!("{}{}", label, pad)
+        });
     }
 
-    // Author (same as owner for now)
-    if show_author {
-        let author = if numeric_ids {
-            entry.uid.to_string()
-        } else {
-            crate::entry::get_username(entry.uid, user_cache)
+    cells.join(" ")
+}
+
+/// Format a long-listing line for a single entry: its columns, separated by
+/// single spaces.
+pub fn format_long_entry(
+    entry: &FileEntry,
+    icon_str: &str,
+    fmt: &LongFormat,
+    user_cache: &mut HashMap<u32, String>,
+    group_cache: &mut HashMap<u32, String>,
+) -> String {
+    let use_color = fmt.use_color;
+    let widths = &fmt.widths;
+    let mut cells: Vec<String> = Vec::with_capacity(fmt.columns.len());
+
+    for (i, &column) in fmt.columns.iter().enumerate() {
+        let cell = match column {
+            Column::Inode => format!("{:>width$}", entry.inode, width = widths.inode),
+            // 512-byte blocks shown as 1K blocks
+            Column::Blocks => format!("{:>width$}", entry.blocks / 2, width = widths.blocks),
+            Column::Icon => icon_str.to_string(),
+            Column::Perms => format_permissions_cell(entry, fmt),
+            Column::Attributes => {
+                // Inode attribute flags (lsattr-style)
+                let letters = entry.attributes.unwrap_or_default().to_letters();
+                if use_color {
+                    colorize_attributes(&letters)
+                } else {
+                    letters
+                }
+            }
+            Column::Links => format!("{:>width$}", entry.nlink, width = widths.nlink),
+            Column::User => colorize_owner(
+                &owner_name(entry.uid, fmt.numeric_ids, user_cache),
+                widths.owner,
+                owner_color(entry.uid, use_color),
+            ),
+            Column::Group => {
+                let group = if fmt.numeric_ids {
+                    entry.gid.to_string()
+                } else {
+                    crate::entry::get_groupname(entry.gid, group_cache)
+                };
+                colorize_owner(&group, widths.group, group_color(entry.gid, use_color))
+            }
+            // Author (same as owner for now)
+            Column::Author => colorize_owner(
+                &owner_name(entry.uid, fmt.numeric_ids, user_cache),
+                widths.owner,
+                None,
+            ),
+            Column::Context => {
+                format!("{:<width$}", format_context(entry), width = widths.context)
+            }
+            Column::Size => {
+                // Size, or "major, minor" for devices
+                let size_str = match entry.device_numbers() {
+                    Some((major, minor)) => format_device(major, minor, widths.major, widths.minor),
+                    None => size_text(entry, fmt.human_readable, fmt.si, fmt.count_dirs),
+                };
+                let pad = " ".repeat(widths.size.saturating_sub(size_str.len()));
+                let is_file = entry.mode & 0o170000 == 0o100000;
+                match fmt.heat.as_ref().and_then(|h| h.size_heat(entry.size)) {
+                    Some(heat) if use_color && is_file => format!(
+                        "{}{}",
+                        pad,
+                        theme::paint_gradient(SIZE_COLD, SIZE_HOT, heat, &size_str)
+                    ),
+                    _ => format!("{}{}", pad, size_str),
+                }
+            }
+            Column::Mtime => format_time_cell(entry.modified, fmt),
+            Column::Atime => format_time_cell(entry.accessed, fmt),
+            Column::Ctime => format_time_cell(entry.changed, fmt),
+            Column::Birth => format_time_cell(entry.created, fmt),
+            Column::Caps => format!("{:<width$}", format_caps(entry), width = widths.caps),
+            Column::Mime => format!("{:<width$}", entry.mime_type(), width = widths.mime),
+            Column::Git => {
+                let marker = entry
+                    .git_status
+                    .as_ref()
+                    .map(|status| status.marker(use_color))
+                    .unwrap_or_default();
+                if i + 1 == fmt.columns.len() {
+                    // Trailing marker: only for files with a status
+                    if marker.is_empty() {
+                        continue;
+                    }
+                    marker
+                } else {
+                    if widths.git == 0 {
+                        continue;
+                    }
+                    let marker_width = entry.git_status.as_ref().map_or(0, |s| s.marker_width());
+                    format!("{}{}", marker, " ".repeat(widths.git - marker_width))
+                }
+            }
+            Column::Name => format_name_cell(entry, fmt),
         };
-        line.push_str(&format!(" {:<width$}", author, width = owner_width));
+        cells.push(cell);
     }
 
-    // Size
-    let size_str = format_size(entry.size, human_readable, si);
-    line.push_str(&format!(" {:>width$}", size_str, width = size_width));
+    cells.join(" ")
+}
 
-    // Timestamp
-    let time_str = format_time(entry.modified);
-    line.push_str(&format!(" {}", time_str));
+/// The owner's name, or uid with `-n`.
+fn owner_name(uid: u32, numeric_ids: bool, user_cache: &mut HashMap<u32, String>) -> String {
+    if numeric_ids {
+        uid.to_string()
+    } else {
+        crate::entry::get_username(uid, user_cache)
+    }
+}
+
+/// Permissions as octal, symbolic or both, followed by the ACL / xattr
+/// marker (padded when another entry in the listing has one).
+fn format_permissions_cell(entry: &FileEntry, fmt: &LongFormat) -> String {
+    let mut cell = String::with_capacity(16);
+    if fmt.perm_style != "symbolic" {
+        let octal = format_octal_permissions(entry.mode);
+        if fmt.use_color {
+            cell.push_str(&colorize_octal_permissions(&octal));
+        } else {
+            cell.push_str(&octal);
+        }
+    }
+    if fmt.perm_style == "both" {
+        cell.push(' ');
+    }
+    if fmt.perm_style != "octal" {
+        let perms = format_permissions(entry.mode, entry.is_dir, entry.is_symlink);
+        if fmt.use_color {
+            cell.push_str(&colorize_permissions(&perms));
+        } else {
+            cell.push_str(&perms);
+        }
+    }
+    if fmt.show_attr_indicator {
+        cell.push(crate::xattr::indicator(&entry.xattrs).unwrap_or(' '));
+    }
+    pad_cell(cell, fmt.widths.perms)
+}
+
+/// A timestamp, colored with `--color-scale` and padded to the header
+/// label's width with `--header`.
+fn format_time_cell(time: Option<SystemTime>, fmt: &LongFormat) -> String {
+    let text = fmt.time_format.format(time);
+    let heat = time.and_then(|t| fmt.heat.as_ref()?.age_heat(t));
+    let text = match heat {
+        Some(heat) if fmt.use_color => theme::paint_gradient(AGE_COLD, AGE_HOT, heat, &text),
+        _ => text,
+    };
+    pad_cell(text, fmt.widths.time)
+}
+
+/// Left-align a (possibly colored) cell to `width` display columns.
+fn pad_cell(mut cell: String, width: usize) -> String {
+    if width > 0 {
+        let len = crate::grid::display_width(&cell);
+        cell.push_str(&" ".repeat(width.saturating_sub(len)));
+    }
+    cell
+}
 
-    // Filename (with color if applicable)
-    line.push(' ');
-    line.push_str(&colorize_filename(entry, use_color));
+/// The file name (colored, and underlined if immutable), symlink target and
+/// classify indicator.
+fn format_name_cell(entry: &FileEntry, fmt: &LongFormat) -> String {
+    let use_color = fmt.use_color;
+    let mut cell = if use_color && entry.is_immutable() {
+        theme::paint(Role::Immutable, &colorize_filename(entry, use_color))
+    } else {
+        colorize_filename(entry, use_color)
+    };
 
-    // Symlink target
     if entry.is_symlink {
         if let Some(ref target) = entry.symlink_target {
-            line.push_str(" -> ");
+            cell.push_str(" -> ");
             if use_color {
-                line.push_str(&format!("\x1b[36m{}\x1b[0m", target));
+                cell.push_str(&theme::paint(Role::SymlinkTarget, target));
             } else {
-                line.push_str(target);
+                cell.push_str(target);
             }
         }
     }
 
-    // Classify indicator
-    // (handled in the caller)
+    if fmt.classify {
+        cell.push_str(classify_indicator(entry, true));
+    } else if fmt.slash_dirs && entry.is_dir {
+        cell.push('/');
+    }
+    cell
+}
 
-    line
+/// The decoded file capabilities of an entry for `--caps`, or `-`.
+pub fn format_caps(entry: &FileEntry) -> String {
+    if !entry.has_capabilities() {
+        return "-".to_string();
+    }
+    crate::xattr::get(&entry.path, crate::xattr::CAPABILITY)
+        .and_then(|value| crate::xattr::decode_capabilities(&value))
+        .unwrap_or_else(|| "?".to_string())
+}
+
+/// The SELinux security context of an entry for `-Z`, or `?` like GNU ls.
+pub fn format_context(entry: &FileEntry) -> String {
+    if !entry.xattrs.iter().any(|n| n == crate::xattr::SELINUX) {
+        return "?".to_string();
+    }
+    crate::xattr::get(&entry.path, crate::xattr::SELINUX)
+        .map(|value| {
+            String::from_utf8_lossy(&value)
+                .trim_end_matches('\0')
+                .to_string()
+        })
+        .unwrap_or_else(|| "?".to_string())
 }
 
 /// Colorize a filename based on its type.
 pub fn colorize_filename(entry: &FileEntry, use_color: bool) -> String {
+    colorize_name(entry, &entry.name, use_color)
+}
+
+/// Colorize `name` (e.g. a truncated form of the entry's name) based on the entry's type.
+pub fn colorize_name(entry: &FileEntry, name: &str, use_color: bool) -> String {
     if !use_color {
-        return entry.name.clone();
+        return name.to_string();
     }
 
-    if entry.is_dir {
-        format!("\x1b[1;34m{}\x1b[0m", entry.name)
+    let sticky = entry.mode & 0o1000 != 0;
+    let other_writable = entry.mode & 0o002 != 0;
+    let is_file = entry.mode & 0o170000 == 0o100000;
+
+    if entry.is_dir && sticky && other_writable {
+        theme::paint(Role::DirStickyOtherWritable, name)
+    } else if entry.is_dir && other_writable {
+        theme::paint(Role::DirOtherWritable, name)
+    } else if entry.is_dir && sticky {
+        theme::paint(Role::DirSticky, name)
+    } else if entry.is_dir {
+        theme::paint(Role::Dir, name)
     } else if entry.is_symlink {
-        format!("\x1b[36m{}\x1b[0m", entry.name)
+        theme::paint(Role::Symlink, name)
+    } else if is_file && entry.mode & 0o4000 != 0 {
+        theme::paint(Role::Setuid, name)
+    } else if is_file && entry.mode & 0o2000 != 0 {
+        theme::paint(Role::Setgid, name)
+    } else if entry.has_capabilities() {
+        theme::paint(Role::Capability, name)
     } else if entry.is_executable {
-        format!("\x1b[1;32m{}\x1b[0m", entry.name)
+        theme::paint(Role::Executable, name)
     } else if entry.is_pipe {
-        format!("\x1b[33m{}\x1b[0m", entry.name)
+        theme::paint(Role::Pipe, name)
     } else if entry.is_socket {
-        format!("\x1b[1;35m{}\x1b[0m", entry.name)
+        theme::paint(Role::Socket, name)
     } else if entry.is_block_device || entry.is_char_device {
-        format!("\x1b[1;33m{}\x1b[0m", entry.name)
+        theme::paint(Role::Device, name)
     } else {
-        // Check extension for known media/archive types
-        let ext = entry.extension.to_lowercase();
+        // Check extension (or the sniffed type's) for known media/archive types
+        let ext = match entry.content_type.and_then(|ct| ct.icon_key) {
+            Some(key) => key[1..].to_string(),
+            None => entry.extension.to_lowercase(),
+        };
         match ext.as_str() {
             // Archives
             "zip" | "tar" | "gz" | "bz2" | "xz" | "7z" | "rar" | "tgz" | "tbz2" | "txz" | "zst"
-            | "deb" | "rpm" | "iso" | "dmg" => {
-                format!("\x1b[1;31m{}\x1b[0m", entry.name)
-            }
+            | "deb" | "rpm" | "iso" | "dmg" => theme::paint(Role::Archive, name),
             // Images
             "png" | "jpg" | "jpeg" | "gif" | "svg" | "webp" | "bmp" | "ico" | "tiff" | "tif"
-            | "psd" | "heic" | "avif" => {
-                format!("\x1b[35m{}\x1b[0m", entry.name)
-            }
+            | "psd" | "heic" | "avif" => theme::paint(Role::Image, name),
             // Audio
             "mp3" | "wav" | "flac" | "aac" | "ogg" | "m4a" | "wma" | "opus" => {
-                format!("\x1b[36m{}\x1b[0m", entry.name)
+                theme::paint(Role::Audio, name)
             }
             // Video
             "mp4" | "mov" | "avi" | "mkv" | "webm" | "flv" | "wmv" | "m4v" | "mpg" | "mpeg" => {
-                format!("\x1b[1;35m{}\x1b[0m", entry.name)
+                theme::paint(Role::Video, name)
             }
-            _ => entry.name.clone(),
+            _ => name.to_string(),
         }
     }
 }
 
+/// Color for the owner column: yours, root's, or someone else's.
+fn owner_color(uid: u32, use_color: bool) -> Option<Role> {
+    if !use_color {
+        None
+    } else if uid == crate::entry::current_uid() {
+        Some(Role::OwnerYou)
+    } else if uid == 0 {
+        None
+    } else {
+        Some(Role::OwnerOther)
+    }
+}
+
+/// Color for the group column: one you belong to, or not.
+fn group_color(gid: u32, use_color: bool) -> Option<Role> {
+    if use_color && crate::entry::in_current_groups(gid) {
+        Some(Role::GroupYours)
+    } else {
+        None
+    }
+}
+
+/// Left-align an owner or group name to `width`, coloring only the name.
+fn colorize_owner(name: &str, width: usize, role: Option<Role>) -> String {
+    let pad = " ".repeat(width.saturating_sub(crate::grid::display_width(name)));
+    match role {
+        Some(role) => format!("{}{}", theme::paint(role, name), pad),
+        None => format!("{}{}", name, pad),
+    }
+}
+
+/// Colorize an octal permission string with the colors `colorize_permissions`
+/// uses for the strongest bit in each digit.
+fn colorize_octal_permissions(octal: &str) -> String {
+    let mut result = String::with_capacity(octal.len() * 10);
+    for (i, c) in octal.chars().enumerate() {
+        let digit = c.to_digit(8).unwrap_or(0);
+        let role = if digit == 0 {
+            Role::PermNone
+        } else if i == 0 {
+            // setuid/setgid/sticky, shown as s/t in symbolic form
+            Role::PermExec
+        } else if digit & 0o2 != 0 {
+            Role::PermWrite
+        } else if digit & 0o1 != 0 {
+            Role::PermExec
+        } else {
+            Role::PermRead
+        };
+        result.push_str(&theme::paint(role, &c.to_string()));
+    }
+    result
+}
+
+/// Colorize an lsattr-style attribute string, highlighting immutable and
+/// append-only flags.
+fn colorize_attributes(letters: &str) -> String {
+    let mut result = String::with_capacity(letters.len() * 10);
+    for c in letters.chars() {
+        let role = match c {
+            'i' => Role::AttrImmutable,
+            'a' => Role::AttrAppend,
+            '-' => Role::PermNone,
+            _ => Role::AttrOther,
+        };
+        result.push_str(&theme::paint(role, &c.to_string()));
+    }
+    result
+}
+
 /// Colorize permission string.
 fn colorize_permissions(perms: &str) -> String {
     let mut result = String::with_capacity(perms.len() * 5);
     for c in perms.chars() {
-        match c {
-            'd' => result.push_str(&format!("\x1b[1;34m{}\x1b[0m", c)),
-            'l' => result.push_str(&format!("\x1b[1;36m{}\x1b[0m", c)),
-            'r' => result.push_str(&format!("\x1b[33m{}\x1b[0m", c)),
-            'w' => result.push_str(&format!("\x1b[31m{}\x1b[0m", c)),
-            'x' | 's' | 't' => result.push_str(&format!("\x1b[1;32m{}\x1b[0m", c)),
-            'S' | 'T' => result.push_str(&format!("\x1b[32m{}\x1b[0m", c)),
-            '-' => result.push_str(&format!("\x1b[90m{}\x1b[0m", c)),
-            _ => result.push(c),
-        }
+        let role = match c {
+            'd' => Role::PermDir,
+            'l' => Role::PermLink,
+            'r' => Role::PermRead,
+            'w' => Role::PermWrite,
+            'x' | 's' | 't' => Role::PermExec,
+            'S' | 'T' => Role::PermSpecial,
+            '-' => Role::PermNone,
+            _ => {
+                result.push(c);
+                continue;
+            }
+        };
+        result.push_str(&theme::paint(role, &c.to_string()));
         // Don't add separator
     }
     result
@@ -332,3 +970,220 @@ pub fn classify_indicator(entry: &FileEntry, full: bool) -> &'static str {
 pub fn total_blocks(entries: &[FileEntry]) -> u64 {
     entries.iter().map(|e| e.blocks).sum::<u64>() / 2 // 512-byte -> 1K blocks
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_expansion() {
+        let cases = [
+            ("%H:%M:%S.%N", None, "%H:%M:%S.000001234"),
+            ("%%N", None, "%%N"),
+            ("%%%N", None, "%%000001234"),
+            ("%b %e", None, "%b %e"),
+            ("%b %e", Some("Mai "), "Mai  %e"),
+            ("%%b", Some("Mai "), "%%b"),
+            ("%b", Some("5%"), "5%%"),
+            ("100%", None, "100%"),
+        ];
+        for (fmt, month, expected) in cases {
+            assert_eq!(
+                expand_format(fmt, 1234, month),
+                expected,
+                "expand_format({:?})",
+                fmt
+            );
+        }
+    }
+
+    #[test]
+    fn owner_padding() {
+        let cases = [
+            ("root", 6, "root  "),
+            ("jürgen", 8, "jürgen  "),
+            ("田中", 6, "田中  "),
+            ("longname", 4, "longname"),
+        ];
+        for (name, width, expected) in cases {
+            assert_eq!(colorize_owner(name, width, None), expected, "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn device_formatting() {
+        let cases = [
+            (1, 3, 1, 1, "1, 3"),
+            (8, 1, 3, 3, "  8,   1"),
+            (259, 65536, 3, 5, "259, 65536"),
+            (4, 64, 0, 0, "4, 64"),
+        ];
+        for (major, minor, mw, nw, expected) in cases {
+            assert_eq!(format_device(major, minor, mw, nw), expected);
+        }
+    }
+
+    #[test]
+    fn permission_modes() {
+        let cases = [
+            (0o100644, "0644", "-rw-r--r--"),
+            (0o100755, "0755", "-rwxr-xr-x"),
+            (0o104755, "4755", "-rwsr-xr-x"),
+            (0o102750, "2750", "-rwxr-s---"),
+            (0o104644, "4644", "-rwSr--r--"),
+            (0o041777, "1777", "drwxrwxrwt"),
+            (0o041776, "1776", "drwxrwxrwT"),
+            (0o107777, "7777", "-rwsrwsrwt"),
+            (0o100000, "0000", "----------"),
+            (0o010600, "0600", "prw-------"),
+        ];
+        for (mode, octal, symbolic) in cases {
+            assert_eq!(format_octal_permissions(mode), octal, "{:o}", mode);
+            let is_dir = mode & 0o170000 == 0o040000;
+            assert_eq!(
+                format_permissions(mode, is_dir, false),
+                symbolic,
+                "{:o}",
+                mode
+            );
+        }
+    }
+
+    #[test]
+    fn header_widths() {
+        use clap::Parser;
+
+        let time_format = TimeFormat::from_args(&Args::parse_from(["lsf"]));
+        let columns = [
+            Column::Perms,
+            Column::Links,
+            Column::User,
+            Column::Size,
+            Column::Mtime,
+            Column::Name,
+        ];
+        let measured = || ColumnWidths {
+            nlink: 1,
+            owner: 10,
+            size: 3,
+            ..ColumnWidths::default()
+        };
+
+        for (perm_style, perms) in [("symbolic", 11), ("octal", 11), ("both", 15)] {
+            let mut widths = measured();
+            widths.fit_header(&columns, perm_style, &time_format);
+            assert_eq!(widths.perms, perms, "{}", perm_style);
+            assert_eq!(widths.nlink, "Links".len());
+            assert_eq!(widths.owner, 10);
+            assert_eq!(widths.size, "Size".len());
+            assert_eq!(widths.time, time_format.width().max("Modified".len()));
+            // Columns not shown are left alone
+            assert_eq!(widths.inode, 0);
+            assert_eq!(widths.group, 0);
+        }
+    }
+
+    #[test]
+    fn header_labels() {
+        let columns = [
+            Column::Inode,
+            Column::Blocks,
+            Column::Icon,
+            Column::Perms,
+            Column::Attributes,
+            Column::Links,
+            Column::User,
+            Column::Group,
+            Column::Author,
+            Column::Context,
+            Column::Size,
+            Column::Mtime,
+            Column::Atime,
+            Column::Ctime,
+            Column::Birth,
+            Column::Caps,
+            Column::Mime,
+            Column::Git,
+            Column::Name,
+        ];
+        let labels: Vec<&str> = columns.iter().map(|&c| header_label(c)).collect();
+        for (column, label) in columns.iter().zip(&labels) {
+            assert_eq!(label.is_empty(), *column == Column::Icon, "{:?}", column);
+            assert_eq!(labels.iter().filter(|l| *l == label).count(), 1);
+        }
+        assert_eq!(header_label(Column::Mtime), "Modified");
+        assert_eq!(header_label(Column::Mime), "MIME type");
+    }
+
+    #[test]
+    fn relative_times() {
+        const DAY: i64 = 86400;
+        let cases = [
+            (0, "just now"),
+            (1, "1 second ago"),
+            (59, "59 seconds ago"),
+            (60, "1 minute ago"),
+            (3599, "59 minutes ago"),
+            (3600, "1 hour ago"),
+            (DAY - 1, "23 hours ago"),
+            (DAY, "1 day ago"),
+            (7 * DAY - 1, "6 days ago"),
+            (7 * DAY, "1 week ago"),
+            (30 * DAY - 1, "4 weeks ago"),
+            (30 * DAY, "1 month ago"),
+            (365 * DAY - 1, "12 months ago"),
+            (365 * DAY, "1 year ago"),
+            (-1, "in 1 second"),
+            (-2 * 3600, "in 2 hours"),
+            (-400 * DAY, "in 1 year"),
+        ];
+        for (age, expected) in cases {
+            let text = format_relative(age);
+            assert_eq!(text, expected, "{}", age);
+            assert!(text.len() <= RELATIVE_WIDTH, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn heat_scale_edges() {
+        let fixed = HeatScale {
+            age: Some((60f64.ln(), (365.0 * 86400f64).ln())),
+            size: Some((1024f64.ln(), ((1u64 << 30) as f64).ln())),
+        };
+        let sizes = [
+            (0, 0.0),
+            (1, 0.0),
+            (1024, 0.0),
+            (1 << 20, 0.5),
+            (1 << 30, 1.0),
+            (u64::MAX, 1.0),
+        ];
+        for (size, heat) in sizes {
+            let got = fixed.size_heat(size).unwrap();
+            assert!((got - heat).abs() < 1e-9, "{}: {}", size, got);
+        }
+
+        let now = SystemTime::now();
+        let ago = |secs| now - std::time::Duration::from_secs(secs);
+        assert_eq!(fixed.age_heat(ago(0)), Some(1.0));
+        assert_eq!(fixed.age_heat(ago(30)), Some(1.0));
+        assert_eq!(fixed.age_heat(ago(2 * 365 * 86400)), Some(0.0));
+        let year = fixed.age_heat(ago(86400)).unwrap();
+        assert!(year > 0.0 && year < 1.0);
+        // Future timestamps are not colored
+        assert_eq!(
+            fixed.age_heat(now + std::time::Duration::from_secs(3600)),
+            None
+        );
+
+        // A listing of equal values sits in the middle; a missing range
+        // leaves the value uncolored
+        let flat = HeatScale {
+            age: None,
+            size: log_range([4096, 4096].into_iter()),
+        };
+        assert_eq!(flat.size_heat(4096), Some(0.5));
+        assert_eq!(flat.age_heat(now), None);
+        assert_eq!(log_range(std::iter::empty()), None);
+    }
+}
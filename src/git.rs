@@ -2,6 +2,8 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
+use crate::theme::{self, Role};
+
 /// Represents the git status of a single file.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GitStatus {
@@ -27,70 +29,26 @@ pub enum GitStatus {
 
 impl GitStatus {
     /// Return a short colored marker string for display.
-    pub fn marker(&self, use_color: bool) -> &'static str {
-        match self {
-            GitStatus::Modified => {
-                if use_color {
-                    "\x1b[33m[M]\x1b[0m"
-                } else {
-                    "[M]"
-                }
-            }
-            GitStatus::Staged => {
-                if use_color {
-                    "\x1b[32m[S]\x1b[0m"
-                } else {
-                    "[S]"
-                }
-            }
-            GitStatus::StagedModified => {
-                if use_color {
-                    "\x1b[33m[SM]\x1b[0m"
-                } else {
-                    "[SM]"
-                }
-            }
-            GitStatus::Untracked => {
-                if use_color {
-                    "\x1b[31m[?]\x1b[0m"
-                } else {
-                    "[?]"
-                }
-            }
-            GitStatus::Renamed => {
-                if use_color {
-                    "\x1b[36m[R]\x1b[0m"
-                } else {
-                    "[R]"
-                }
-            }
-            GitStatus::Deleted => {
-                if use_color {
-                    "\x1b[31m[D]\x1b[0m"
-                } else {
-                    "[D]"
-                }
-            }
-            GitStatus::Conflicted => {
-                if use_color {
-                    "\x1b[1;31m[U]\x1b[0m"
-                } else {
-                    "[U]"
-                }
-            }
-            GitStatus::Ignored => {
-                if use_color {
-                    "\x1b[90m[I]\x1b[0m"
-                } else {
-                    "[I]"
-                }
-            }
-            GitStatus::Clean => "",
+    pub fn marker(&self, use_color: bool) -> String {
+        let (text, role) = match self {
+            GitStatus::Modified => ("[M]", Role::GitModified),
+            GitStatus::Staged => ("[S]", Role::GitStaged),
+            GitStatus::StagedModified => ("[SM]", Role::GitModified),
+            GitStatus::Untracked => ("[?]", Role::GitUntracked),
+            GitStatus::Renamed => ("[R]", Role::GitRenamed),
+            GitStatus::Deleted => ("[D]", Role::GitDeleted),
+            GitStatus::Conflicted => ("[U]", Role::GitConflicted),
+            GitStatus::Ignored => ("[I]", Role::GitIgnored),
+            GitStatus::Clean => return String::new(),
+        };
+        if use_color {
+            theme::paint(role, text)
+        } else {
+            text.to_string()
         }
     }
 
     /// Return the display width of the marker (excluding ANSI codes).
-    #[allow(dead_code)]
     pub fn marker_width(&self) -> usize {
         match self {
             GitStatus::Clean => 0,
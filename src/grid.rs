@@ -1,4 +1,4 @@
-use unicode_width::UnicodeWidthStr;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 /// Calculate the display width of a string, ignoring ANSI escape sequences.
 pub fn display_width(s: &str) -> usize {
@@ -6,6 +6,15 @@ pub fn display_width(s: &str) -> usize {
     UnicodeWidthStr::width(stripped.as_str())
 }
 
+/// A formatted entry for the grid and comma views, with the terminal columns
+/// it takes. That can differ from `display_width(text)` when icons are drawn
+/// in a fixed number of cells (`--icon-width` with `--icon-padding=none`).
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub text: String,
+    pub width: usize,
+}
+
 /// Strip ANSI escape sequences from a string.
 pub fn strip_ansi(s: &str) -> String {
     let mut result = String::with_capacity(s.len());
@@ -28,12 +37,68 @@ pub fn strip_ansi(s: &str) -> String {
     result
 }
 
+/// Shorten a file name to at most `max` display columns by replacing its
+/// middle with an ellipsis. The extension is kept intact whenever it fits.
+pub fn truncate_middle(name: &str, max: usize) -> String {
+    if UnicodeWidthStr::width(name) <= max {
+        return name.to_string();
+    }
+    if max <= 1 {
+        return "\u{2026}".to_string();
+    }
+
+    // Split off the extension (but not the leading dot of a hidden file)
+    let (stem, ext) = match name.rfind('.') {
+        Some(pos) if pos > 0 => name.split_at(pos),
+        _ => (name, ""),
+    };
+    let ext_width = UnicodeWidthStr::width(ext);
+    let (stem, ext, ext_width) = if ext_width + 2 <= max {
+        (stem, ext, ext_width)
+    } else {
+        (name, "", 0)
+    };
+
+    // Room left for the stem once the ellipsis and extension are placed
+    let budget = max - 1 - ext_width;
+    let head_budget = budget.div_ceil(2);
+    let tail_budget = budget - head_budget;
+
+    let mut head = String::new();
+    let mut width = 0;
+    for c in stem.chars() {
+        let w = UnicodeWidthChar::width(c).unwrap_or(0);
+        if width + w > head_budget {
+            break;
+        }
+        head.push(c);
+        width += w;
+    }
+
+    let mut tail: Vec<char> = Vec::new();
+    let mut width = 0;
+    for c in stem.chars().rev() {
+        let w = UnicodeWidthChar::width(c).unwrap_or(0);
+        if width + w > tail_budget {
+            break;
+        }
+        tail.push(c);
+        width += w;
+    }
+
+    let mut result = head;
+    result.push('\u{2026}');
+    result.extend(tail.iter().rev());
+    result.push_str(ext);
+    result
+}
+
 /// Format entries into a multi-column grid.
 /// `items` - pre-formatted strings (with ANSI colors).
 /// `term_width` - terminal width in columns.
 /// `tabsize` - tab stop width.
 /// `by_rows` - if true, fill rows first (like `ls -x`); if false, fill columns first.
-pub fn format_grid(items: &[String], term_width: usize, tabsize: usize, by_rows: bool) -> String {
+pub fn format_grid(items: &[Item], term_width: usize, tabsize: usize, by_rows: bool) -> String {
     if items.is_empty() {
         return String::new();
     }
@@ -41,7 +106,7 @@ pub fn format_grid(items: &[String], term_width: usize, tabsize: usize, by_rows:
     let tabsize = if tabsize == 0 { 8 } else { tabsize };
 
     // Find the maximum display width
-    let max_len = items.iter().map(|s| display_width(s)).max().unwrap_or(0);
+    let max_len = items.iter().map(|item| item.width).max().unwrap_or(0);
 
     // Calculate column width (align to tab stops)
     let col_width = if max_len == 0 {
@@ -69,7 +134,7 @@ pub fn format_grid(items: &[String], term_width: usize, tabsize: usize, by_rows:
             }
 
             let item = &items[index];
-            let d_len = display_width(item);
+            let d_len = item.width;
 
             // Determine if there's a next item in this row
             let has_next = if by_rows {
@@ -78,7 +143,7 @@ pub fn format_grid(items: &[String], term_width: usize, tabsize: usize, by_rows:
                 j != cols - 1 && (index + rows) < items.len()
             };
 
-            output.push_str(item);
+            output.push_str(&item.text);
 
             if has_next {
                 let offset = d_len % col_width;
@@ -104,17 +169,17 @@ pub fn format_grid(items: &[String], term_width: usize, tabsize: usize, by_rows:
 }
 
 /// Format entries as one-per-line.
-pub fn format_single_column(items: &[String], terminator: char) -> String {
+pub fn format_single_column(items: &[Item], terminator: char) -> String {
     let mut output = String::with_capacity(items.len() * 40);
     for item in items {
-        output.push_str(item);
+        output.push_str(&item.text);
         output.push(terminator);
     }
     output
 }
 
 /// Format entries as comma-separated.
-pub fn format_comma(items: &[String], term_width: usize) -> String {
+pub fn format_comma(items: &[Item], term_width: usize) -> String {
     if items.is_empty() {
         return String::new();
     }
@@ -123,18 +188,8 @@ pub fn format_comma(items: &[String], term_width: usize) -> String {
     let mut line_len: usize = 0;
 
     for (i, item) in items.iter().enumerate() {
-        let d_len = display_width(item);
-        let separator = if i < items.len() - 1 { ", " } else { "" };
-        let needed = d_len + separator.len();
-
-        if line_len > 0 && line_len + needed > term_width {
-            output.push('\n');
-            line_len = 0;
-        }
-
-        output.push_str(item);
-        output.push_str(separator);
-        line_len += needed;
+        let is_last = i == items.len() - 1;
+        push_comma_item(&mut output, item, is_last, &mut line_len, term_width);
     }
 
     if !output.ends_with('\n') {
@@ -144,6 +199,28 @@ pub fn format_comma(items: &[String], term_width: usize) -> String {
     output
 }
 
+/// Append one item of a comma-separated listing, wrapping before it if it
+/// would overflow `term_width`. `line_len` tracks the current line's width.
+pub fn push_comma_item(
+    output: &mut String,
+    item: &Item,
+    is_last: bool,
+    line_len: &mut usize,
+    term_width: usize,
+) {
+    let separator = if is_last { "" } else { ", " };
+    let needed = item.width + separator.len();
+
+    if *line_len > 0 && *line_len + needed > term_width {
+        output.push('\n');
+        *line_len = 0;
+    }
+
+    output.push_str(&item.text);
+    output.push_str(separator);
+    *line_len += needed;
+}
+
 /// Get terminal width, with optional override.
 pub fn get_terminal_width(override_width: Option<usize>) -> usize {
     if let Some(w) = override_width {
@@ -153,3 +230,72 @@ pub fn get_terminal_width(override_width: Option<usize>) -> usize {
         .map(|(w, _)| w.0 as usize)
         .unwrap_or(80)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{display_width, format_grid, truncate_middle, Item};
+
+    #[test]
+    fn truncate_middle_cases() {
+        let cases = [
+            ("short.txt", 20, "short.txt"),
+            ("exact.txt", 9, "exact.txt"),
+            ("report-final-version.pdf", 12, "repo…ion.pdf"),
+            ("a.verylongextension", 8, "a.ve…ion"),
+            (".bashrc_backup", 6, ".ba…up"),
+            ("noextension", 5, "no…on"),
+            ("日本語ファイル名.txt", 10, "日…名.txt"),
+            ("anything", 1, "…"),
+            ("anything", 0, "…"),
+        ];
+        for (name, max, expected) in cases {
+            assert_eq!(
+                truncate_middle(name, max),
+                expected,
+                "truncate_middle({:?}, {})",
+                name,
+                max
+            );
+        }
+    }
+
+    #[test]
+    fn truncate_middle_fits_width() {
+        let names = [
+            "archive.tar.gz",
+            "日本語ファイル名.txt",
+            ".hidden-config-file",
+            "x",
+        ];
+        for name in names {
+            for max in 1..=display_width(name) {
+                let truncated = truncate_middle(name, max);
+                assert!(
+                    display_width(&truncated) <= max,
+                    "{:?} at {} gave {:?}",
+                    name,
+                    max,
+                    truncated
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn grid_uses_item_widths() {
+        // A private-use character in a file name is one cell wide
+        assert_eq!(display_width("a\u{e0b0}b"), 3);
+
+        let item = |text: &str, width| Item {
+            text: text.to_string(),
+            width,
+        };
+        // Same text, but the first item claims an extra icon cell
+        let items = [item("ab", 3), item("cd", 2)];
+        assert_eq!(format_grid(&items, 80, 4, true), "ab cd\n");
+        let items = [item("ab", 4), item("cd", 2)];
+        assert_eq!(format_grid(&items, 80, 4, true), "ab    cd\n");
+        let items = [item("ab", 2), item("cd", 2), item("ef", 2)];
+        assert_eq!(format_grid(&items, 8, 4, false), "ab  ef\ncd\n");
+    }
+}
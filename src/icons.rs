@@ -1,7 +1,12 @@
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::sync::OnceLock;
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::cli::Args;
 
 /// An icon entry: the Nerd Font glyph and its ANSI color code.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 pub struct IconEntry {
     pub icon: &'static str,
     pub color: &'static str,
@@ -12,14 +17,43 @@ impl IconEntry {
         Self { icon, color }
     }
 
-    /// Return the icon wrapped in ANSI color escape codes.
+    /// Return the icon wrapped in ANSI color escape codes, recolored by the
+    /// active theme and padded to the icon cell width.
     pub fn colored(&self) -> String {
-        format!("\x1b[{}m{}\x1b[0m", self.color, self.icon)
+        let mut s = crate::theme::paint_sgr(self.color, self.icon);
+        s.push_str(&self.padding());
+        s
     }
 
-    /// Return the icon without color.
+    /// Return the icon without color, padded to the icon cell width.
     pub fn plain(&self) -> String {
-        self.icon.to_string()
+        let mut s = self.icon.to_string();
+        s.push_str(&self.padding());
+        s
+    }
+
+    /// Terminal columns the icon takes, padding included. With
+    /// `--icon-padding=none` the terminal is trusted to advance a Nerd Font
+    /// glyph by the `--icon-width` cells, whatever its Unicode width.
+    pub fn width(&self) -> usize {
+        let layout = icon_layout();
+        let glyph = UnicodeWidthStr::width(self.icon);
+        match layout.cell_width {
+            Some(cell) if layout.fill => glyph.max(cell),
+            Some(cell) if is_private_use_glyph(self.icon) => cell,
+            _ => glyph,
+        }
+    }
+
+    /// Spaces filling the rest of the icon cell with `--icon-padding=fill`.
+    fn padding(&self) -> String {
+        let layout = icon_layout();
+        match layout.cell_width {
+            Some(cell) if layout.fill => {
+                " ".repeat(cell.saturating_sub(UnicodeWidthStr::width(self.icon)))
+            }
+            _ => String::new(),
+        }
     }
 }
 
@@ -37,502 +71,839 @@ pub const SYMLINK_ICON: IconEntry = IconEntry::new("\u{f0c1}", "36"); //
 pub const EXEC_ICON: IconEntry = IconEntry::new("\u{f489}", "1;32"); //
 
 // Pipe / socket / device icons
-pub const PIPE_ICON: IconEntry = IconEntry::new("\u{f731}", "33"); //
-pub const SOCKET_ICON: IconEntry = IconEntry::new("\u{f6a7}", "35"); //
+pub const PIPE_ICON: IconEntry = IconEntry::new("\u{f07e5}", "33"); //
+pub const SOCKET_ICON: IconEntry = IconEntry::new("\u{f1e6}", "35"); //
 pub const BLOCK_DEV_ICON: IconEntry = IconEntry::new("\u{f0a0}", "1;33"); //
 pub const CHAR_DEV_ICON: IconEntry = IconEntry::new("\u{e601}", "1;33"); //
 
-/// Build the complete extension -> IconEntry map.
-/// Uses a function so the map is built once and reused.
-pub fn build_icon_map() -> HashMap<&'static str, IconEntry> {
-    let mut m = HashMap::with_capacity(256);
-
-    // ── Common Config Files (exact filename match, lowercase) ──────────
-    m.insert(".gitignore", IconEntry::new("\u{e702}", "91"));
-    m.insert(".gitconfig", IconEntry::new("\u{e702}", "91"));
-    m.insert(".gitattributes", IconEntry::new("\u{e702}", "91"));
-    m.insert(".gitmodules", IconEntry::new("\u{e702}", "91"));
-    m.insert(".gitlab-ci.yml", IconEntry::new("\u{e702}", "91"));
-    m.insert(".github", IconEntry::new("\u{e702}", "90"));
-    m.insert("dockerfile", IconEntry::new("\u{f308}", "94"));
-    m.insert(".dockerignore", IconEntry::new("\u{f308}", "94"));
-    m.insert("docker-compose.yml", IconEntry::new("\u{f308}", "94"));
-    m.insert("docker-compose.yaml", IconEntry::new("\u{f308}", "94"));
-    m.insert("license", IconEntry::new("\u{f718}", "90"));
-    m.insert("license.md", IconEntry::new("\u{f718}", "90"));
-    m.insert("license.txt", IconEntry::new("\u{f718}", "90"));
-    m.insert("readme.md", IconEntry::new("\u{f48a}", "96"));
-    m.insert("readme", IconEntry::new("\u{f48a}", "96"));
-    m.insert("readme.txt", IconEntry::new("\u{f48a}", "96"));
-    m.insert("makefile", IconEntry::new("\u{f085}", "90"));
-    m.insert("cmakelists.txt", IconEntry::new("\u{f085}", "90"));
-    m.insert(".editorconfig", IconEntry::new("\u{f085}", "90"));
-    m.insert(".eslintrc", IconEntry::new("\u{f085}", "95"));
-    m.insert(".eslintrc.js", IconEntry::new("\u{f085}", "95"));
-    m.insert(".eslintrc.json", IconEntry::new("\u{f085}", "95"));
-    m.insert(".eslintrc.yml", IconEntry::new("\u{f085}", "95"));
-    m.insert(".prettierrc", IconEntry::new("\u{f085}", "95"));
-    m.insert(".prettierrc.js", IconEntry::new("\u{f085}", "95"));
-    m.insert(".prettierrc.json", IconEntry::new("\u{f085}", "95"));
-    m.insert(".prettierrc.yml", IconEntry::new("\u{f085}", "95"));
-    m.insert(".npmrc", IconEntry::new("\u{e71e}", "91"));
-    m.insert(".nvmrc", IconEntry::new("\u{e718}", "92"));
-    m.insert(".babelrc", IconEntry::new("\u{f085}", "93"));
-    m.insert(".browserslistrc", IconEntry::new("\u{f085}", "90"));
-    m.insert("tsconfig.json", IconEntry::new("\u{e628}", "94"));
-    m.insert("jsconfig.json", IconEntry::new("\u{e781}", "93"));
-    m.insert(".env", IconEntry::new("\u{f462}", "90"));
-    m.insert(".env.local", IconEntry::new("\u{f462}", "90"));
-    m.insert(".env.development", IconEntry::new("\u{f462}", "90"));
-    m.insert(".env.production", IconEntry::new("\u{f462}", "90"));
-    m.insert(".env.test", IconEntry::new("\u{f462}", "90"));
-    m.insert("vagrantfile", IconEntry::new("\u{f085}", "94"));
-    m.insert("gruntfile.js", IconEntry::new("\u{e74c}", "93"));
-    m.insert("gulpfile.js", IconEntry::new("\u{e610}", "91"));
-    m.insert("webpack.config.js", IconEntry::new("\u{f72b}", "94"));
-    m.insert("rollup.config.js", IconEntry::new("\u{f085}", "91"));
-    m.insert("vite.config.js", IconEntry::new("\u{f085}", "95"));
-    m.insert("vite.config.ts", IconEntry::new("\u{f085}", "95"));
-    m.insert("next.config.js", IconEntry::new("\u{f085}", "37"));
-    m.insert("nuxt.config.js", IconEntry::new("\u{f085}", "92"));
-    m.insert("angular.json", IconEntry::new("\u{e753}", "91"));
-    m.insert("tailwind.config.js", IconEntry::new("\u{f085}", "36"));
-    m.insert("tailwind.config.ts", IconEntry::new("\u{f085}", "36"));
-    m.insert("postcss.config.js", IconEntry::new("\u{f085}", "91"));
-    m.insert("jest.config.js", IconEntry::new("\u{f085}", "91"));
-    m.insert("jest.config.ts", IconEntry::new("\u{f085}", "91"));
-    m.insert("vitest.config.ts", IconEntry::new("\u{f085}", "92"));
-    m.insert("cargo.toml", IconEntry::new("\u{e7a8}", "91"));
-    m.insert("cargo.lock", IconEntry::new("\u{f023}", "90"));
-
-    // ── Package Managers & Dependencies ────────────────────────────────
-    m.insert("package.json", IconEntry::new("\u{e71e}", "91"));
-    m.insert("package-lock.json", IconEntry::new("\u{f023}", "90"));
-    m.insert("yarn.lock", IconEntry::new("\u{f023}", "90"));
-    m.insert("pnpm-lock.yaml", IconEntry::new("\u{f023}", "90"));
-    m.insert("composer.json", IconEntry::new("\u{e608}", "90"));
-    m.insert("composer.lock", IconEntry::new("\u{f023}", "90"));
-    m.insert("gemfile", IconEntry::new("\u{e21e}", "91"));
-    m.insert("gemfile.lock", IconEntry::new("\u{f023}", "90"));
-    m.insert("requirements.txt", IconEntry::new("\u{e606}", "92"));
-    m.insert("pipfile", IconEntry::new("\u{e606}", "92"));
-    m.insert("pipfile.lock", IconEntry::new("\u{f023}", "90"));
-    m.insert("pyproject.toml", IconEntry::new("\u{e606}", "92"));
-    m.insert("setup.py", IconEntry::new("\u{e606}", "92"));
-    m.insert("setup.cfg", IconEntry::new("\u{e606}", "92"));
-    m.insert("go.mod", IconEntry::new("\u{e626}", "96"));
-    m.insert("go.sum", IconEntry::new("\u{f023}", "90"));
-    m.insert("pubspec.yaml", IconEntry::new("\u{e798}", "94"));
-    m.insert("pubspec.lock", IconEntry::new("\u{f023}", "90"));
-    m.insert("build.gradle", IconEntry::new("\u{e660}", "94"));
-    m.insert("build.gradle.kts", IconEntry::new("\u{e660}", "94"));
-    m.insert("settings.gradle", IconEntry::new("\u{e660}", "94"));
-    m.insert("pom.xml", IconEntry::new("\u{e674}", "91"));
-    m.insert("mix.exs", IconEntry::new("\u{e62d}", "95"));
-    m.insert("mix.lock", IconEntry::new("\u{f023}", "90"));
-    m.insert("flake.nix", IconEntry::new("\u{f313}", "94"));
-    m.insert("flake.lock", IconEntry::new("\u{f023}", "90"));
-    m.insert("default.nix", IconEntry::new("\u{f313}", "94"));
-    m.insert("shell.nix", IconEntry::new("\u{f313}", "94"));
-
-    // ── CI/CD ──────────────────────────────────────────────────────────
-    m.insert(".travis.yml", IconEntry::new("\u{e77e}", "91"));
-    m.insert("jenkinsfile", IconEntry::new("\u{e767}", "91"));
-    m.insert(".circleci", IconEntry::new("\u{f085}", "92"));
-
-    // ── Programming Languages (by extension) ───────────────────────────
-    // Python
-    m.insert(".py", IconEntry::new("\u{e606}", "92"));
-    m.insert(".pyc", IconEntry::new("\u{e606}", "90"));
-    m.insert(".pyo", IconEntry::new("\u{e606}", "90"));
-    m.insert(".pyd", IconEntry::new("\u{e606}", "90"));
-    m.insert(".pyw", IconEntry::new("\u{e606}", "92"));
-    m.insert(".pyx", IconEntry::new("\u{e606}", "92"));
-    m.insert(".pxd", IconEntry::new("\u{e606}", "92"));
-    m.insert(".ipynb", IconEntry::new("\u{e606}", "93"));
-
-    // JavaScript / TypeScript
-    m.insert(".js", IconEntry::new("\u{e781}", "93"));
-    m.insert(".jsx", IconEntry::new("\u{e7ba}", "96"));
-    m.insert(".ts", IconEntry::new("\u{e628}", "94"));
-    m.insert(".tsx", IconEntry::new("\u{e7ba}", "96"));
-    m.insert(".mjs", IconEntry::new("\u{e781}", "93"));
-    m.insert(".cjs", IconEntry::new("\u{e781}", "93"));
-
-    // Java / JVM
-    m.insert(".java", IconEntry::new("\u{e738}", "93"));
-    m.insert(".class", IconEntry::new("\u{e738}", "90"));
-    m.insert(".jar", IconEntry::new("\u{e738}", "93"));
-    m.insert(".war", IconEntry::new("\u{e738}", "93"));
-    m.insert(".gradle", IconEntry::new("\u{e660}", "94"));
-
-    // C / C++
-    m.insert(".c", IconEntry::new("\u{e61e}", "96"));
-    m.insert(".cpp", IconEntry::new("\u{e61d}", "96"));
-    m.insert(".cc", IconEntry::new("\u{e61d}", "96"));
-    m.insert(".cxx", IconEntry::new("\u{e61d}", "96"));
-    m.insert(".c++", IconEntry::new("\u{e61d}", "96"));
-    m.insert(".h", IconEntry::new("\u{e61e}", "96"));
-    m.insert(".hpp", IconEntry::new("\u{e61d}", "96"));
-    m.insert(".hh", IconEntry::new("\u{e61d}", "96"));
-    m.insert(".hxx", IconEntry::new("\u{e61d}", "96"));
-    m.insert(".h++", IconEntry::new("\u{e61d}", "96"));
-
-    // Rust
-    m.insert(".rs", IconEntry::new("\u{e7a8}", "91"));
-    m.insert(".rlib", IconEntry::new("\u{e7a8}", "90"));
-
-    // Go
-    m.insert(".go", IconEntry::new("\u{e626}", "96"));
-
-    // PHP
-    m.insert(".php", IconEntry::new("\u{e608}", "95"));
-    m.insert(".phtml", IconEntry::new("\u{e608}", "95"));
-
-    // Ruby
-    m.insert(".rb", IconEntry::new("\u{e21e}", "91"));
-    m.insert(".erb", IconEntry::new("\u{e21e}", "91"));
-    m.insert(".rake", IconEntry::new("\u{e21e}", "91"));
-    m.insert(".gemspec", IconEntry::new("\u{e21e}", "91"));
-
-    // Swift
-    m.insert(".swift", IconEntry::new("\u{e755}", "93"));
-
-    // Kotlin
-    m.insert(".kt", IconEntry::new("\u{e634}", "93"));
-    m.insert(".kts", IconEntry::new("\u{e634}", "93"));
-
-    // Lua
-    m.insert(".lua", IconEntry::new("\u{e620}", "94"));
-
-    // Dart
-    m.insert(".dart", IconEntry::new("\u{e798}", "94"));
-
-    // Haskell
-    m.insert(".hs", IconEntry::new("\u{e61f}", "95"));
-    m.insert(".lhs", IconEntry::new("\u{e61f}", "95"));
-    m.insert(".cabal", IconEntry::new("\u{e61f}", "95"));
-
-    // R
-    m.insert(".r", IconEntry::new("\u{f25d}", "94"));
-    m.insert(".rmd", IconEntry::new("\u{f25d}", "94"));
-
-    // Julia
-    m.insert(".jl", IconEntry::new("\u{e624}", "95"));
-
-    // Perl
-    m.insert(".pl", IconEntry::new("\u{e769}", "94"));
-    m.insert(".pm", IconEntry::new("\u{e769}", "94"));
-
-    // Shell
-    m.insert(".sh", IconEntry::new("\u{f489}", "32"));
-    m.insert(".bash", IconEntry::new("\u{f489}", "32"));
-    m.insert(".zsh", IconEntry::new("\u{f489}", "32"));
-    m.insert(".fish", IconEntry::new("\u{f489}", "32"));
-    m.insert(".ksh", IconEntry::new("\u{f489}", "32"));
-    m.insert(".csh", IconEntry::new("\u{f489}", "32"));
-    m.insert(".tcsh", IconEntry::new("\u{f489}", "32"));
-    m.insert(".ps1", IconEntry::new("\u{f489}", "94"));
-    m.insert(".psm1", IconEntry::new("\u{f489}", "94"));
-    m.insert(".psd1", IconEntry::new("\u{f489}", "94"));
-    m.insert(".bat", IconEntry::new("\u{f489}", "92"));
-    m.insert(".cmd", IconEntry::new("\u{f489}", "92"));
-
-    // C# / .NET
-    m.insert(".cs", IconEntry::new("\u{f81a}", "94"));
-    m.insert(".csx", IconEntry::new("\u{f81a}", "94"));
-    m.insert(".csproj", IconEntry::new("\u{f81a}", "94"));
-    m.insert(".sln", IconEntry::new("\u{f81a}", "95"));
-
-    // F#
-    m.insert(".fs", IconEntry::new("\u{e7a7}", "94"));
-    m.insert(".fsx", IconEntry::new("\u{e7a7}", "94"));
-    m.insert(".fsi", IconEntry::new("\u{e7a7}", "94"));
-    m.insert(".fsproj", IconEntry::new("\u{e7a7}", "94"));
-
-    // VB
-    m.insert(".vb", IconEntry::new("\u{f81a}", "94"));
-    m.insert(".vbs", IconEntry::new("\u{f81a}", "94"));
-
-    // Scala
-    m.insert(".scala", IconEntry::new("\u{e737}", "91"));
-    m.insert(".sc", IconEntry::new("\u{e737}", "91"));
-    m.insert(".sbt", IconEntry::new("\u{e737}", "91"));
-
-    // Clojure
-    m.insert(".clj", IconEntry::new("\u{e768}", "92"));
-    m.insert(".cljs", IconEntry::new("\u{e768}", "92"));
-    m.insert(".cljc", IconEntry::new("\u{e768}", "92"));
-    m.insert(".edn", IconEntry::new("\u{e768}", "92"));
-
-    // Erlang
-    m.insert(".erl", IconEntry::new("\u{e7b1}", "95"));
-    m.insert(".hrl", IconEntry::new("\u{e7b1}", "95"));
-
-    // Elixir
-    m.insert(".ex", IconEntry::new("\u{e62d}", "95"));
-    m.insert(".exs", IconEntry::new("\u{e62d}", "95"));
-    m.insert(".eex", IconEntry::new("\u{e62d}", "95"));
-    m.insert(".heex", IconEntry::new("\u{e62d}", "95"));
-    m.insert(".leex", IconEntry::new("\u{e62d}", "95"));
-
-    // Vim
-    m.insert(".vim", IconEntry::new("\u{e62b}", "92"));
-    m.insert(".vimrc", IconEntry::new("\u{e62b}", "92"));
-
-    // Zig
-    m.insert(".zig", IconEntry::new("\u{f0e7}", "93"));
-
-    // Nim
-    m.insert(".nim", IconEntry::new("\u{f0e7}", "93"));
-    m.insert(".nimble", IconEntry::new("\u{f0e7}", "93"));
-
-    // Crystal
-    m.insert(".cr", IconEntry::new("\u{e62f}", "37"));
-
-    // OCaml
-    m.insert(".ml", IconEntry::new("\u{03bb}", "93")); // λ
-    m.insert(".mli", IconEntry::new("\u{03bb}", "93"));
-
-    // Verilog / VHDL
-    m.insert(".v", IconEntry::new("\u{f085}", "94"));
-    m.insert(".sv", IconEntry::new("\u{f085}", "94"));
-    m.insert(".vhd", IconEntry::new("\u{f085}", "94"));
-    m.insert(".vhdl", IconEntry::new("\u{f085}", "94"));
-
-    // Assembly
-    m.insert(".asm", IconEntry::new("\u{f471}", "91"));
-    m.insert(".s", IconEntry::new("\u{f471}", "91"));
-    m.insert(".S", IconEntry::new("\u{f471}", "91"));
-
-    // Nix
-    m.insert(".nix", IconEntry::new("\u{f313}", "94"));
-
-    // Terraform
-    m.insert(".tf", IconEntry::new("\u{f085}", "95"));
-    m.insert(".tfvars", IconEntry::new("\u{f085}", "95"));
-
-    // ── Web & Frontend ─────────────────────────────────────────────────
-    m.insert(".html", IconEntry::new("\u{e736}", "95"));
-    m.insert(".htm", IconEntry::new("\u{e736}", "95"));
-    m.insert(".xhtml", IconEntry::new("\u{e736}", "95"));
-    m.insert(".css", IconEntry::new("\u{e749}", "36"));
-    m.insert(".scss", IconEntry::new("\u{e749}", "36"));
-    m.insert(".sass", IconEntry::new("\u{e749}", "36"));
-    m.insert(".less", IconEntry::new("\u{e749}", "36"));
-    m.insert(".styl", IconEntry::new("\u{e749}", "36"));
-    m.insert(".vue", IconEntry::new("\u{e6a0}", "92"));
-    m.insert(".svelte", IconEntry::new("\u{e697}", "91"));
-    m.insert(".astro", IconEntry::new("\u{e697}", "91"));
-    m.insert(".wasm", IconEntry::new("\u{e6a1}", "95"));
-
-    // ── Data & Config ──────────────────────────────────────────────────
-    m.insert(".json", IconEntry::new("\u{e60b}", "94"));
-    m.insert(".jsonc", IconEntry::new("\u{e60b}", "94"));
-    m.insert(".json5", IconEntry::new("\u{e60b}", "94"));
-    m.insert(".jsonl", IconEntry::new("\u{e60b}", "94"));
-    m.insert(".ndjson", IconEntry::new("\u{e60b}", "94"));
-    m.insert(".xml", IconEntry::new("\u{e619}", "91"));
-    m.insert(".xsl", IconEntry::new("\u{e619}", "91"));
-    m.insert(".xsd", IconEntry::new("\u{e619}", "91"));
-    m.insert(".yml", IconEntry::new("\u{e60b}", "94"));
-    m.insert(".yaml", IconEntry::new("\u{e60b}", "94"));
-    m.insert(".toml", IconEntry::new("\u{e60b}", "94"));
-    m.insert(".ini", IconEntry::new("\u{f085}", "90"));
-    m.insert(".env", IconEntry::new("\u{f462}", "90"));
-    m.insert(".conf", IconEntry::new("\u{f085}", "90"));
-    m.insert(".cfg", IconEntry::new("\u{f085}", "90"));
-    m.insert(".properties", IconEntry::new("\u{f085}", "90"));
-    m.insert(".sql", IconEntry::new("\u{f1c0}", "93"));
-    m.insert(".db", IconEntry::new("\u{f1c0}", "93"));
-    m.insert(".sqlite", IconEntry::new("\u{f1c0}", "93"));
-    m.insert(".sqlite3", IconEntry::new("\u{f1c0}", "93"));
-    m.insert(".graphql", IconEntry::new("\u{e662}", "95"));
-    m.insert(".gql", IconEntry::new("\u{e662}", "95"));
-    m.insert(".proto", IconEntry::new("\u{f085}", "94"));
-    m.insert(".csv", IconEntry::new("\u{f1c3}", "92"));
-    m.insert(".tsv", IconEntry::new("\u{f1c3}", "92"));
-    m.insert(".hcl", IconEntry::new("\u{f085}", "95"));
-
-    // ── Documents ──────────────────────────────────────────────────────
-    m.insert(".md", IconEntry::new("\u{e73e}", "96"));
-    m.insert(".markdown", IconEntry::new("\u{e73e}", "96"));
-    m.insert(".mdx", IconEntry::new("\u{e73e}", "96"));
-    m.insert(".txt", IconEntry::new("\u{f15c}", "37"));
-    m.insert(".pdf", IconEntry::new("\u{f1c1}", "31"));
-    m.insert(".doc", IconEntry::new("\u{f1c2}", "94"));
-    m.insert(".docx", IconEntry::new("\u{f1c2}", "94"));
-    m.insert(".xls", IconEntry::new("\u{f1c3}", "92"));
-    m.insert(".xlsx", IconEntry::new("\u{f1c3}", "92"));
-    m.insert(".ppt", IconEntry::new("\u{f1c4}", "91"));
-    m.insert(".pptx", IconEntry::new("\u{f1c4}", "91"));
-    m.insert(".log", IconEntry::new("\u{f15c}", "90"));
-    m.insert(".tex", IconEntry::new("\u{f15c}", "92"));
-    m.insert(".bib", IconEntry::new("\u{f15c}", "93"));
-    m.insert(".rtf", IconEntry::new("\u{f1c2}", "94"));
-    m.insert(".odt", IconEntry::new("\u{f1c2}", "94"));
-    m.insert(".ods", IconEntry::new("\u{f1c3}", "92"));
-    m.insert(".odp", IconEntry::new("\u{f1c4}", "91"));
-    m.insert(".epub", IconEntry::new("\u{f02d}", "91"));
-    m.insert(".rst", IconEntry::new("\u{e73e}", "96"));
-    m.insert(".org", IconEntry::new("\u{e633}", "92"));
-    m.insert(".adoc", IconEntry::new("\u{e73e}", "96"));
-    m.insert(".man", IconEntry::new("\u{f15c}", "90"));
-
-    // ── Media - Images ─────────────────────────────────────────────────
-    m.insert(".png", IconEntry::new("\u{f1c5}", "93"));
-    m.insert(".jpg", IconEntry::new("\u{f1c5}", "93"));
-    m.insert(".jpeg", IconEntry::new("\u{f1c5}", "93"));
-    m.insert(".svg", IconEntry::new("\u{f1c5}", "93"));
-    m.insert(".gif", IconEntry::new("\u{f1c5}", "93"));
-    m.insert(".webp", IconEntry::new("\u{f1c5}", "93"));
-    m.insert(".bmp", IconEntry::new("\u{f1c5}", "93"));
-    m.insert(".ico", IconEntry::new("\u{f1c5}", "93"));
-    m.insert(".tiff", IconEntry::new("\u{f1c5}", "93"));
-    m.insert(".tif", IconEntry::new("\u{f1c5}", "93"));
-    m.insert(".psd", IconEntry::new("\u{e7b8}", "94"));
-    m.insert(".ai", IconEntry::new("\u{e7b4}", "93"));
-    m.insert(".eps", IconEntry::new("\u{f1c5}", "93"));
-    m.insert(".raw", IconEntry::new("\u{f1c5}", "93"));
-    m.insert(".cr2", IconEntry::new("\u{f1c5}", "93"));
-    m.insert(".nef", IconEntry::new("\u{f1c5}", "93"));
-    m.insert(".heic", IconEntry::new("\u{f1c5}", "93"));
-    m.insert(".heif", IconEntry::new("\u{f1c5}", "93"));
-    m.insert(".avif", IconEntry::new("\u{f1c5}", "93"));
-    m.insert(".jxl", IconEntry::new("\u{f1c5}", "93"));
-
-    // ── Media - Audio ──────────────────────────────────────────────────
-    m.insert(".mp3", IconEntry::new("\u{f1c7}", "95"));
-    m.insert(".wav", IconEntry::new("\u{f1c7}", "95"));
-    m.insert(".flac", IconEntry::new("\u{f1c7}", "95"));
-    m.insert(".aac", IconEntry::new("\u{f1c7}", "95"));
-    m.insert(".ogg", IconEntry::new("\u{f1c7}", "95"));
-    m.insert(".m4a", IconEntry::new("\u{f1c7}", "95"));
-    m.insert(".wma", IconEntry::new("\u{f1c7}", "95"));
-    m.insert(".opus", IconEntry::new("\u{f1c7}", "95"));
-    m.insert(".mid", IconEntry::new("\u{f1c7}", "95"));
-    m.insert(".midi", IconEntry::new("\u{f1c7}", "95"));
-    m.insert(".aiff", IconEntry::new("\u{f1c7}", "95"));
-    m.insert(".ape", IconEntry::new("\u{f1c7}", "95"));
-
-    // ── Media - Video ──────────────────────────────────────────────────
-    m.insert(".mp4", IconEntry::new("\u{f1c8}", "95"));
-    m.insert(".mov", IconEntry::new("\u{f1c8}", "95"));
-    m.insert(".avi", IconEntry::new("\u{f1c8}", "95"));
-    m.insert(".mkv", IconEntry::new("\u{f1c8}", "95"));
-    m.insert(".webm", IconEntry::new("\u{f1c8}", "95"));
-    m.insert(".flv", IconEntry::new("\u{f1c8}", "95"));
-    m.insert(".wmv", IconEntry::new("\u{f1c8}", "95"));
-    m.insert(".m4v", IconEntry::new("\u{f1c8}", "95"));
-    m.insert(".mpg", IconEntry::new("\u{f1c8}", "95"));
-    m.insert(".mpeg", IconEntry::new("\u{f1c8}", "95"));
-    m.insert(".3gp", IconEntry::new("\u{f1c8}", "95"));
-    m.insert(".ts", IconEntry::new("\u{f1c8}", "95")); // NOTE: conflicts with TypeScript, extension wins last
-
-    // ── Compressed & Archives ──────────────────────────────────────────
-    m.insert(".zip", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".tar", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".gz", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".bz2", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".xz", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".zst", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".lz", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".lz4", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".lzma", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".7z", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".rar", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".tgz", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".tbz2", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".txz", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".iso", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".dmg", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".img", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".cab", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".cpio", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".ar", IconEntry::new("\u{f1c6}", "95"));
-    m.insert(".deb", IconEntry::new("\u{f187}", "91"));
-    m.insert(".rpm", IconEntry::new("\u{f187}", "91"));
-    m.insert(".apk", IconEntry::new("\u{e70e}", "92"));
-    m.insert(".snap", IconEntry::new("\u{f187}", "92"));
-    m.insert(".flatpak", IconEntry::new("\u{f187}", "94"));
-    m.insert(".appimage", IconEntry::new("\u{f187}", "92"));
-
-    // ── Executables & Binaries ─────────────────────────────────────────
-    m.insert(".exe", IconEntry::new("\u{f17a}", "92"));
-    m.insert(".app", IconEntry::new("\u{f179}", "92"));
-    m.insert(".dll", IconEntry::new("\u{f17a}", "90"));
-    m.insert(".so", IconEntry::new("\u{f17c}", "90"));
-    m.insert(".dylib", IconEntry::new("\u{f179}", "90"));
-    m.insert(".bin", IconEntry::new("\u{f489}", "90"));
-    m.insert(".msi", IconEntry::new("\u{f17a}", "92"));
-    m.insert(".out", IconEntry::new("\u{f489}", "90"));
-    m.insert(".elf", IconEntry::new("\u{f489}", "90"));
-    m.insert(".o", IconEntry::new("\u{f471}", "90"));
-    m.insert(".a", IconEntry::new("\u{f471}", "90"));
-
-    // ── Fonts ──────────────────────────────────────────────────────────
-    m.insert(".ttf", IconEntry::new("\u{f031}", "37"));
-    m.insert(".otf", IconEntry::new("\u{f031}", "37"));
-    m.insert(".woff", IconEntry::new("\u{f031}", "37"));
-    m.insert(".woff2", IconEntry::new("\u{f031}", "37"));
-    m.insert(".eot", IconEntry::new("\u{f031}", "37"));
-
-    // ── 3D & CAD ───────────────────────────────────────────────────────
-    m.insert(".obj", IconEntry::new("\u{f1b2}", "95"));
-    m.insert(".fbx", IconEntry::new("\u{f1b2}", "95"));
-    m.insert(".stl", IconEntry::new("\u{f1b2}", "95"));
-    m.insert(".blend", IconEntry::new("\u{f1b2}", "93"));
-    m.insert(".3ds", IconEntry::new("\u{f1b2}", "95"));
-    m.insert(".dae", IconEntry::new("\u{f1b2}", "95"));
-    m.insert(".gltf", IconEntry::new("\u{f1b2}", "95"));
-    m.insert(".glb", IconEntry::new("\u{f1b2}", "95"));
-    m.insert(".usdz", IconEntry::new("\u{f1b2}", "95"));
-
-    // ── Security / Crypto ──────────────────────────────────────────────
-    m.insert(".key", IconEntry::new("\u{f084}", "91"));
-    m.insert(".pem", IconEntry::new("\u{f084}", "91"));
-    m.insert(".crt", IconEntry::new("\u{f084}", "91"));
-    m.insert(".cert", IconEntry::new("\u{f084}", "91"));
-    m.insert(".cer", IconEntry::new("\u{f084}", "91"));
-    m.insert(".ca", IconEntry::new("\u{f084}", "91"));
-    m.insert(".pub", IconEntry::new("\u{f084}", "91"));
-    m.insert(".gpg", IconEntry::new("\u{f084}", "91"));
-    m.insert(".pgp", IconEntry::new("\u{f084}", "91"));
-    m.insert(".asc", IconEntry::new("\u{f084}", "91"));
-    m.insert(".sig", IconEntry::new("\u{f084}", "91"));
-    m.insert(".p12", IconEntry::new("\u{f084}", "91"));
-    m.insert(".pfx", IconEntry::new("\u{f084}", "91"));
-    m.insert(".csr", IconEntry::new("\u{f084}", "91"));
-
-    // ── Other / Misc ───────────────────────────────────────────────────
-    m.insert(".lock", IconEntry::new("\u{f023}", "90"));
-    m.insert(".bak", IconEntry::new("\u{f0e2}", "90"));
-    m.insert(".tmp", IconEntry::new("\u{f0e2}", "90"));
-    m.insert(".temp", IconEntry::new("\u{f0e2}", "90"));
-    m.insert(".swp", IconEntry::new("\u{f0e2}", "90"));
-    m.insert(".swo", IconEntry::new("\u{f0e2}", "90"));
-    m.insert(".cache", IconEntry::new("\u{f0e2}", "90"));
-    m.insert(".pid", IconEntry::new("\u{f085}", "90"));
-    m.insert(".sock", IconEntry::new("\u{f6a7}", "35"));
-    m.insert(".patch", IconEntry::new("\u{f440}", "92"));
-    m.insert(".diff", IconEntry::new("\u{f440}", "92"));
-
-    // TypeScript should take priority over video .ts
-    // Re-insert TypeScript to override the video .ts entry
-    m.insert(".ts", IconEntry::new("\u{e628}", "94"));
-
-    m
+/// Exact file names and extensions (with leading dot) mapped to their icons.
+/// Keys are lowercase ASCII and must stay sorted by byte value so lookups can
+/// binary search the table; this is checked at compile time below.
+static ICON_TABLE: &[(&str, IconEntry)] = &[
+    (".3ds", IconEntry::new("\u{f1b2}", "95")),
+    (".3gp", IconEntry::new("\u{f1c8}", "95")),
+    (".7z", IconEntry::new("\u{f1c6}", "95")),
+    (".a", IconEntry::new("\u{f471}", "90")),
+    (".aac", IconEntry::new("\u{f1c7}", "95")),
+    (".adoc", IconEntry::new("\u{e73e}", "96")),
+    (".ai", IconEntry::new("\u{e7b4}", "93")),
+    (".aiff", IconEntry::new("\u{f1c7}", "95")),
+    (".ape", IconEntry::new("\u{f1c7}", "95")),
+    (".apk", IconEntry::new("\u{e70e}", "92")),
+    (".app", IconEntry::new("\u{f179}", "92")),
+    (".appimage", IconEntry::new("\u{f187}", "92")),
+    (".ar", IconEntry::new("\u{f1c6}", "95")),
+    (".asc", IconEntry::new("\u{f084}", "91")),
+    (".asm", IconEntry::new("\u{f471}", "91")),
+    (".astro", IconEntry::new("\u{e697}", "91")),
+    (".avi", IconEntry::new("\u{f1c8}", "95")),
+    (".avif", IconEntry::new("\u{f1c5}", "93")),
+    (".babelrc", IconEntry::new("\u{f085}", "93")),
+    (".bak", IconEntry::new("\u{f0e2}", "90")),
+    (".bash", IconEntry::new("\u{f489}", "32")),
+    (".bat", IconEntry::new("\u{f489}", "92")),
+    (".bib", IconEntry::new("\u{f15c}", "93")),
+    (".bin", IconEntry::new("\u{f489}", "90")),
+    (".blend", IconEntry::new("\u{f1b2}", "93")),
+    (".bmp", IconEntry::new("\u{f1c5}", "93")),
+    (".browserslistrc", IconEntry::new("\u{f085}", "90")),
+    (".bz2", IconEntry::new("\u{f1c6}", "95")),
+    (".c", IconEntry::new("\u{e61e}", "96")),
+    (".c++", IconEntry::new("\u{e61d}", "96")),
+    (".ca", IconEntry::new("\u{f084}", "91")),
+    (".cab", IconEntry::new("\u{f1c6}", "95")),
+    (".cabal", IconEntry::new("\u{e61f}", "95")),
+    (".cache", IconEntry::new("\u{f0e2}", "90")),
+    (".cc", IconEntry::new("\u{e61d}", "96")),
+    (".cer", IconEntry::new("\u{f084}", "91")),
+    (".cert", IconEntry::new("\u{f084}", "91")),
+    (".cfg", IconEntry::new("\u{f085}", "90")),
+    (".circleci", IconEntry::new("\u{f085}", "92")),
+    (".cjs", IconEntry::new("\u{e781}", "93")),
+    (".class", IconEntry::new("\u{e738}", "90")),
+    (".clj", IconEntry::new("\u{e768}", "92")),
+    (".cljc", IconEntry::new("\u{e768}", "92")),
+    (".cljs", IconEntry::new("\u{e768}", "92")),
+    (".cmd", IconEntry::new("\u{f489}", "92")),
+    (".conf", IconEntry::new("\u{f085}", "90")),
+    (".cpio", IconEntry::new("\u{f1c6}", "95")),
+    (".cpp", IconEntry::new("\u{e61d}", "96")),
+    (".cr", IconEntry::new("\u{e62f}", "37")),
+    (".cr2", IconEntry::new("\u{f1c5}", "93")),
+    (".crt", IconEntry::new("\u{f084}", "91")),
+    (".cs", IconEntry::new("\u{f031b}", "94")),
+    (".csh", IconEntry::new("\u{f489}", "32")),
+    (".csproj", IconEntry::new("\u{f031b}", "94")),
+    (".csr", IconEntry::new("\u{f084}", "91")),
+    (".css", IconEntry::new("\u{e749}", "36")),
+    (".csv", IconEntry::new("\u{f1c3}", "92")),
+    (".csx", IconEntry::new("\u{f031b}", "94")),
+    (".cxx", IconEntry::new("\u{e61d}", "96")),
+    (".dae", IconEntry::new("\u{f1b2}", "95")),
+    (".dart", IconEntry::new("\u{e798}", "94")),
+    (".db", IconEntry::new("\u{f1c0}", "93")),
+    (".deb", IconEntry::new("\u{f187}", "91")),
+    (".diff", IconEntry::new("\u{f440}", "92")),
+    (".dll", IconEntry::new("\u{f17a}", "90")),
+    (".dmg", IconEntry::new("\u{f1c6}", "95")),
+    (".doc", IconEntry::new("\u{f1c2}", "94")),
+    (".dockerignore", IconEntry::new("\u{f308}", "94")),
+    (".docx", IconEntry::new("\u{f1c2}", "94")),
+    (".dylib", IconEntry::new("\u{f179}", "90")),
+    (".editorconfig", IconEntry::new("\u{f085}", "90")),
+    (".edn", IconEntry::new("\u{e768}", "92")),
+    (".eex", IconEntry::new("\u{e62d}", "95")),
+    (".elf", IconEntry::new("\u{f489}", "90")),
+    (".env", IconEntry::new("\u{f462}", "90")),
+    (".env.development", IconEntry::new("\u{f462}", "90")),
+    (".env.local", IconEntry::new("\u{f462}", "90")),
+    (".env.production", IconEntry::new("\u{f462}", "90")),
+    (".env.test", IconEntry::new("\u{f462}", "90")),
+    (".eot", IconEntry::new("\u{f031}", "37")),
+    (".eps", IconEntry::new("\u{f1c5}", "93")),
+    (".epub", IconEntry::new("\u{f02d}", "91")),
+    (".erb", IconEntry::new("\u{e21e}", "91")),
+    (".erl", IconEntry::new("\u{e7b1}", "95")),
+    (".eslintrc", IconEntry::new("\u{f085}", "95")),
+    (".eslintrc.js", IconEntry::new("\u{f085}", "95")),
+    (".eslintrc.json", IconEntry::new("\u{f085}", "95")),
+    (".eslintrc.yml", IconEntry::new("\u{f085}", "95")),
+    (".ex", IconEntry::new("\u{e62d}", "95")),
+    (".exe", IconEntry::new("\u{f17a}", "92")),
+    (".exs", IconEntry::new("\u{e62d}", "95")),
+    (".fbx", IconEntry::new("\u{f1b2}", "95")),
+    (".fish", IconEntry::new("\u{f489}", "32")),
+    (".flac", IconEntry::new("\u{f1c7}", "95")),
+    (".flatpak", IconEntry::new("\u{f187}", "94")),
+    (".flv", IconEntry::new("\u{f1c8}", "95")),
+    (".fs", IconEntry::new("\u{e7a7}", "94")),
+    (".fsi", IconEntry::new("\u{e7a7}", "94")),
+    (".fsproj", IconEntry::new("\u{e7a7}", "94")),
+    (".fsx", IconEntry::new("\u{e7a7}", "94")),
+    (".gemspec", IconEntry::new("\u{e21e}", "91")),
+    (".gif", IconEntry::new("\u{f1c5}", "93")),
+    (".gitattributes", IconEntry::new("\u{e702}", "91")),
+    (".gitconfig", IconEntry::new("\u{e702}", "91")),
+    (".github", IconEntry::new("\u{e702}", "90")),
+    (".gitignore", IconEntry::new("\u{e702}", "91")),
+    (".gitlab-ci.yml", IconEntry::new("\u{e702}", "91")),
+    (".gitmodules", IconEntry::new("\u{e702}", "91")),
+    (".glb", IconEntry::new("\u{f1b2}", "95")),
+    (".gltf", IconEntry::new("\u{f1b2}", "95")),
+    (".go", IconEntry::new("\u{e626}", "96")),
+    (".gpg", IconEntry::new("\u{f084}", "91")),
+    (".gql", IconEntry::new("\u{e662}", "95")),
+    (".gradle", IconEntry::new("\u{e660}", "94")),
+    (".graphql", IconEntry::new("\u{e662}", "95")),
+    (".gz", IconEntry::new("\u{f1c6}", "95")),
+    (".h", IconEntry::new("\u{e61e}", "96")),
+    (".h++", IconEntry::new("\u{e61d}", "96")),
+    (".hcl", IconEntry::new("\u{f085}", "95")),
+    (".heex", IconEntry::new("\u{e62d}", "95")),
+    (".heic", IconEntry::new("\u{f1c5}", "93")),
+    (".heif", IconEntry::new("\u{f1c5}", "93")),
+    (".hh", IconEntry::new("\u{e61d}", "96")),
+    (".hpp", IconEntry::new("\u{e61d}", "96")),
+    (".hrl", IconEntry::new("\u{e7b1}", "95")),
+    (".hs", IconEntry::new("\u{e61f}", "95")),
+    (".htm", IconEntry::new("\u{e736}", "95")),
+    (".html", IconEntry::new("\u{e736}", "95")),
+    (".hxx", IconEntry::new("\u{e61d}", "96")),
+    (".ico", IconEntry::new("\u{f1c5}", "93")),
+    (".img", IconEntry::new("\u{f1c6}", "95")),
+    (".ini", IconEntry::new("\u{f085}", "90")),
+    (".ipynb", IconEntry::new("\u{e606}", "93")),
+    (".iso", IconEntry::new("\u{f1c6}", "95")),
+    (".jar", IconEntry::new("\u{e738}", "93")),
+    (".java", IconEntry::new("\u{e738}", "93")),
+    (".jl", IconEntry::new("\u{e624}", "95")),
+    (".jpeg", IconEntry::new("\u{f1c5}", "93")),
+    (".jpg", IconEntry::new("\u{f1c5}", "93")),
+    (".js", IconEntry::new("\u{e781}", "93")),
+    (".json", IconEntry::new("\u{e60b}", "94")),
+    (".json5", IconEntry::new("\u{e60b}", "94")),
+    (".jsonc", IconEntry::new("\u{e60b}", "94")),
+    (".jsonl", IconEntry::new("\u{e60b}", "94")),
+    (".jsx", IconEntry::new("\u{e7ba}", "96")),
+    (".jxl", IconEntry::new("\u{f1c5}", "93")),
+    (".key", IconEntry::new("\u{f084}", "91")),
+    (".ksh", IconEntry::new("\u{f489}", "32")),
+    (".kt", IconEntry::new("\u{e634}", "93")),
+    (".kts", IconEntry::new("\u{e634}", "93")),
+    (".leex", IconEntry::new("\u{e62d}", "95")),
+    (".less", IconEntry::new("\u{e749}", "36")),
+    (".lhs", IconEntry::new("\u{e61f}", "95")),
+    (".lock", IconEntry::new("\u{f023}", "90")),
+    (".log", IconEntry::new("\u{f15c}", "90")),
+    (".lua", IconEntry::new("\u{e620}", "94")),
+    (".lz", IconEntry::new("\u{f1c6}", "95")),
+    (".lz4", IconEntry::new("\u{f1c6}", "95")),
+    (".lzma", IconEntry::new("\u{f1c6}", "95")),
+    (".m4a", IconEntry::new("\u{f1c7}", "95")),
+    (".m4v", IconEntry::new("\u{f1c8}", "95")),
+    (".man", IconEntry::new("\u{f15c}", "90")),
+    (".markdown", IconEntry::new("\u{e73e}", "96")),
+    (".md", IconEntry::new("\u{e73e}", "96")),
+    (".mdx", IconEntry::new("\u{e73e}", "96")),
+    (".mid", IconEntry::new("\u{f1c7}", "95")),
+    (".midi", IconEntry::new("\u{f1c7}", "95")),
+    (".mjs", IconEntry::new("\u{e781}", "93")),
+    (".mkv", IconEntry::new("\u{f1c8}", "95")),
+    (".ml", IconEntry::new("\u{03bb}", "93")),
+    (".mli", IconEntry::new("\u{03bb}", "93")),
+    (".mov", IconEntry::new("\u{f1c8}", "95")),
+    (".mp3", IconEntry::new("\u{f1c7}", "95")),
+    (".mp4", IconEntry::new("\u{f1c8}", "95")),
+    (".mpeg", IconEntry::new("\u{f1c8}", "95")),
+    (".mpg", IconEntry::new("\u{f1c8}", "95")),
+    (".msi", IconEntry::new("\u{f17a}", "92")),
+    (".ndjson", IconEntry::new("\u{e60b}", "94")),
+    (".nef", IconEntry::new("\u{f1c5}", "93")),
+    (".nim", IconEntry::new("\u{f0e7}", "93")),
+    (".nimble", IconEntry::new("\u{f0e7}", "93")),
+    (".nix", IconEntry::new("\u{f313}", "94")),
+    (".npmrc", IconEntry::new("\u{e71e}", "91")),
+    (".nvmrc", IconEntry::new("\u{e718}", "92")),
+    (".o", IconEntry::new("\u{f471}", "90")),
+    (".obj", IconEntry::new("\u{f1b2}", "95")),
+    (".odp", IconEntry::new("\u{f1c4}", "91")),
+    (".ods", IconEntry::new("\u{f1c3}", "92")),
+    (".odt", IconEntry::new("\u{f1c2}", "94")),
+    (".ogg", IconEntry::new("\u{f1c7}", "95")),
+    (".opus", IconEntry::new("\u{f1c7}", "95")),
+    (".org", IconEntry::new("\u{e633}", "92")),
+    (".otf", IconEntry::new("\u{f031}", "37")),
+    (".out", IconEntry::new("\u{f489}", "90")),
+    (".p12", IconEntry::new("\u{f084}", "91")),
+    (".patch", IconEntry::new("\u{f440}", "92")),
+    (".pdf", IconEntry::new("\u{f1c1}", "31")),
+    (".pem", IconEntry::new("\u{f084}", "91")),
+    (".pfx", IconEntry::new("\u{f084}", "91")),
+    (".pgp", IconEntry::new("\u{f084}", "91")),
+    (".php", IconEntry::new("\u{e608}", "95")),
+    (".phtml", IconEntry::new("\u{e608}", "95")),
+    (".pid", IconEntry::new("\u{f085}", "90")),
+    (".pl", IconEntry::new("\u{e769}", "94")),
+    (".pm", IconEntry::new("\u{e769}", "94")),
+    (".png", IconEntry::new("\u{f1c5}", "93")),
+    (".ppt", IconEntry::new("\u{f1c4}", "91")),
+    (".pptx", IconEntry::new("\u{f1c4}", "91")),
+    (".prettierrc", IconEntry::new("\u{f085}", "95")),
+    (".prettierrc.js", IconEntry::new("\u{f085}", "95")),
+    (".prettierrc.json", IconEntry::new("\u{f085}", "95")),
+    (".prettierrc.yml", IconEntry::new("\u{f085}", "95")),
+    (".properties", IconEntry::new("\u{f085}", "90")),
+    (".proto", IconEntry::new("\u{f085}", "94")),
+    (".ps1", IconEntry::new("\u{f489}", "94")),
+    (".psd", IconEntry::new("\u{e7b8}", "94")),
+    (".psd1", IconEntry::new("\u{f489}", "94")),
+    (".psm1", IconEntry::new("\u{f489}", "94")),
+    (".pub", IconEntry::new("\u{f084}", "91")),
+    (".pxd", IconEntry::new("\u{e606}", "92")),
+    (".py", IconEntry::new("\u{e606}", "92")),
+    (".pyc", IconEntry::new("\u{e606}", "90")),
+    (".pyd", IconEntry::new("\u{e606}", "90")),
+    (".pyo", IconEntry::new("\u{e606}", "90")),
+    (".pyw", IconEntry::new("\u{e606}", "92")),
+    (".pyx", IconEntry::new("\u{e606}", "92")),
+    (".r", IconEntry::new("\u{f25d}", "94")),
+    (".rake", IconEntry::new("\u{e21e}", "91")),
+    (".rar", IconEntry::new("\u{f1c6}", "95")),
+    (".raw", IconEntry::new("\u{f1c5}", "93")),
+    (".rb", IconEntry::new("\u{e21e}", "91")),
+    (".rlib", IconEntry::new("\u{e7a8}", "90")),
+    (".rmd", IconEntry::new("\u{f25d}", "94")),
+    (".rpm", IconEntry::new("\u{f187}", "91")),
+    (".rs", IconEntry::new("\u{e7a8}", "91")),
+    (".rst", IconEntry::new("\u{e73e}", "96")),
+    (".rtf", IconEntry::new("\u{f1c2}", "94")),
+    (".s", IconEntry::new("\u{f471}", "91")),
+    (".sass", IconEntry::new("\u{e749}", "36")),
+    (".sbt", IconEntry::new("\u{e737}", "91")),
+    (".sc", IconEntry::new("\u{e737}", "91")),
+    (".scala", IconEntry::new("\u{e737}", "91")),
+    (".scss", IconEntry::new("\u{e749}", "36")),
+    (".sh", IconEntry::new("\u{f489}", "32")),
+    (".sig", IconEntry::new("\u{f084}", "91")),
+    (".sln", IconEntry::new("\u{f031b}", "95")),
+    (".snap", IconEntry::new("\u{f187}", "92")),
+    (".so", IconEntry::new("\u{f17c}", "90")),
+    (".sock", IconEntry::new("\u{f1e6}", "35")),
+    (".sql", IconEntry::new("\u{f1c0}", "93")),
+    (".sqlite", IconEntry::new("\u{f1c0}", "93")),
+    (".sqlite3", IconEntry::new("\u{f1c0}", "93")),
+    (".stl", IconEntry::new("\u{f1b2}", "95")),
+    (".styl", IconEntry::new("\u{e749}", "36")),
+    (".sv", IconEntry::new("\u{f085}", "94")),
+    (".svelte", IconEntry::new("\u{e697}", "91")),
+    (".svg", IconEntry::new("\u{f1c5}", "93")),
+    (".swift", IconEntry::new("\u{e755}", "93")),
+    (".swo", IconEntry::new("\u{f0e2}", "90")),
+    (".swp", IconEntry::new("\u{f0e2}", "90")),
+    (".tar", IconEntry::new("\u{f1c6}", "95")),
+    (".tbz2", IconEntry::new("\u{f1c6}", "95")),
+    (".tcsh", IconEntry::new("\u{f489}", "32")),
+    (".temp", IconEntry::new("\u{f0e2}", "90")),
+    (".tex", IconEntry::new("\u{f15c}", "92")),
+    (".tf", IconEntry::new("\u{f085}", "95")),
+    (".tfvars", IconEntry::new("\u{f085}", "95")),
+    (".tgz", IconEntry::new("\u{f1c6}", "95")),
+    (".tif", IconEntry::new("\u{f1c5}", "93")),
+    (".tiff", IconEntry::new("\u{f1c5}", "93")),
+    (".tmp", IconEntry::new("\u{f0e2}", "90")),
+    (".toml", IconEntry::new("\u{e60b}", "94")),
+    (".travis.yml", IconEntry::new("\u{e77e}", "91")),
+    (".ts", IconEntry::new("\u{e628}", "94")),
+    (".tsv", IconEntry::new("\u{f1c3}", "92")),
+    (".tsx", IconEntry::new("\u{e7ba}", "96")),
+    (".ttf", IconEntry::new("\u{f031}", "37")),
+    (".txt", IconEntry::new("\u{f15c}", "37")),
+    (".txz", IconEntry::new("\u{f1c6}", "95")),
+    (".usdz", IconEntry::new("\u{f1b2}", "95")),
+    (".v", IconEntry::new("\u{f085}", "94")),
+    (".vb", IconEntry::new("\u{f031b}", "94")),
+    (".vbs", IconEntry::new("\u{f031b}", "94")),
+    (".vhd", IconEntry::new("\u{f085}", "94")),
+    (".vhdl", IconEntry::new("\u{f085}", "94")),
+    (".vim", IconEntry::new("\u{e62b}", "92")),
+    (".vimrc", IconEntry::new("\u{e62b}", "92")),
+    (".vue", IconEntry::new("\u{e6a0}", "92")),
+    (".war", IconEntry::new("\u{e738}", "93")),
+    (".wasm", IconEntry::new("\u{e6a1}", "95")),
+    (".wav", IconEntry::new("\u{f1c7}", "95")),
+    (".webm", IconEntry::new("\u{f1c8}", "95")),
+    (".webp", IconEntry::new("\u{f1c5}", "93")),
+    (".wma", IconEntry::new("\u{f1c7}", "95")),
+    (".wmv", IconEntry::new("\u{f1c8}", "95")),
+    (".woff", IconEntry::new("\u{f031}", "37")),
+    (".woff2", IconEntry::new("\u{f031}", "37")),
+    (".xhtml", IconEntry::new("\u{e736}", "95")),
+    (".xls", IconEntry::new("\u{f1c3}", "92")),
+    (".xlsx", IconEntry::new("\u{f1c3}", "92")),
+    (".xml", IconEntry::new("\u{e619}", "91")),
+    (".xsd", IconEntry::new("\u{e619}", "91")),
+    (".xsl", IconEntry::new("\u{e619}", "91")),
+    (".xz", IconEntry::new("\u{f1c6}", "95")),
+    (".yaml", IconEntry::new("\u{e60b}", "94")),
+    (".yml", IconEntry::new("\u{e60b}", "94")),
+    (".zig", IconEntry::new("\u{f0e7}", "93")),
+    (".zip", IconEntry::new("\u{f1c6}", "95")),
+    (".zsh", IconEntry::new("\u{f489}", "32")),
+    (".zst", IconEntry::new("\u{f1c6}", "95")),
+    ("angular.json", IconEntry::new("\u{e753}", "91")),
+    ("build.gradle", IconEntry::new("\u{e660}", "94")),
+    ("build.gradle.kts", IconEntry::new("\u{e660}", "94")),
+    ("cargo.lock", IconEntry::new("\u{f023}", "90")),
+    ("cargo.toml", IconEntry::new("\u{e7a8}", "91")),
+    ("cmakelists.txt", IconEntry::new("\u{f085}", "90")),
+    ("composer.json", IconEntry::new("\u{e608}", "90")),
+    ("composer.lock", IconEntry::new("\u{f023}", "90")),
+    ("default.nix", IconEntry::new("\u{f313}", "94")),
+    ("docker-compose.yaml", IconEntry::new("\u{f308}", "94")),
+    ("docker-compose.yml", IconEntry::new("\u{f308}", "94")),
+    ("dockerfile", IconEntry::new("\u{f308}", "94")),
+    ("flake.lock", IconEntry::new("\u{f023}", "90")),
+    ("flake.nix", IconEntry::new("\u{f313}", "94")),
+    ("gemfile", IconEntry::new("\u{e21e}", "91")),
+    ("gemfile.lock", IconEntry::new("\u{f023}", "90")),
+    ("go.mod", IconEntry::new("\u{e626}", "96")),
+    ("go.sum", IconEntry::new("\u{f023}", "90")),
+    ("gruntfile.js", IconEntry::new("\u{e74c}", "93")),
+    ("gulpfile.js", IconEntry::new("\u{e610}", "91")),
+    ("jenkinsfile", IconEntry::new("\u{e767}", "91")),
+    ("jest.config.js", IconEntry::new("\u{f085}", "91")),
+    ("jest.config.ts", IconEntry::new("\u{f085}", "91")),
+    ("jsconfig.json", IconEntry::new("\u{e781}", "93")),
+    ("license", IconEntry::new("\u{f0fc3}", "90")),
+    ("license.md", IconEntry::new("\u{f0fc3}", "90")),
+    ("license.txt", IconEntry::new("\u{f0fc3}", "90")),
+    ("makefile", IconEntry::new("\u{f085}", "90")),
+    ("mix.exs", IconEntry::new("\u{e62d}", "95")),
+    ("mix.lock", IconEntry::new("\u{f023}", "90")),
+    ("next.config.js", IconEntry::new("\u{f085}", "37")),
+    ("nuxt.config.js", IconEntry::new("\u{f085}", "92")),
+    ("package-lock.json", IconEntry::new("\u{f023}", "90")),
+    ("package.json", IconEntry::new("\u{e71e}", "91")),
+    ("pipfile", IconEntry::new("\u{e606}", "92")),
+    ("pipfile.lock", IconEntry::new("\u{f023}", "90")),
+    ("pnpm-lock.yaml", IconEntry::new("\u{f023}", "90")),
+    ("pom.xml", IconEntry::new("\u{e674}", "91")),
+    ("postcss.config.js", IconEntry::new("\u{f085}", "91")),
+    ("pubspec.lock", IconEntry::new("\u{f023}", "90")),
+    ("pubspec.yaml", IconEntry::new("\u{e798}", "94")),
+    ("pyproject.toml", IconEntry::new("\u{e606}", "92")),
+    ("readme", IconEntry::new("\u{f48a}", "96")),
+    ("readme.md", IconEntry::new("\u{f48a}", "96")),
+    ("readme.txt", IconEntry::new("\u{f48a}", "96")),
+    ("requirements.txt", IconEntry::new("\u{e606}", "92")),
+    ("rollup.config.js", IconEntry::new("\u{f085}", "91")),
+    ("settings.gradle", IconEntry::new("\u{e660}", "94")),
+    ("setup.cfg", IconEntry::new("\u{e606}", "92")),
+    ("setup.py", IconEntry::new("\u{e606}", "92")),
+    ("shell.nix", IconEntry::new("\u{f313}", "94")),
+    ("tailwind.config.js", IconEntry::new("\u{f085}", "36")),
+    ("tailwind.config.ts", IconEntry::new("\u{f085}", "36")),
+    ("tsconfig.json", IconEntry::new("\u{e628}", "94")),
+    ("vagrantfile", IconEntry::new("\u{f085}", "94")),
+    ("vite.config.js", IconEntry::new("\u{f085}", "95")),
+    ("vite.config.ts", IconEntry::new("\u{f085}", "95")),
+    ("vitest.config.ts", IconEntry::new("\u{f085}", "92")),
+    ("webpack.config.js", IconEntry::new("\u{f072b}", "94")),
+    ("yarn.lock", IconEntry::new("\u{f023}", "90")),
+];
+
+const _: () = assert!(
+    is_strictly_sorted(ICON_TABLE),
+    "ICON_TABLE keys must be unique and sorted"
+);
+
+/// Compile-time check that table keys are in strictly increasing byte order.
+const fn is_strictly_sorted(table: &[(&str, IconEntry)]) -> bool {
+    let mut i = 1;
+    while i < table.len() {
+        let a = table[i - 1].0.as_bytes();
+        let b = table[i].0.as_bytes();
+        let mut j = 0;
+        loop {
+            if j == a.len() || j == b.len() {
+                // One key is a prefix of the other: the shorter must come first
+                if a.len() >= b.len() {
+                    return false;
+                }
+                break;
+            }
+            if a[j] != b[j] {
+                if a[j] > b[j] {
+                    return false;
+                }
+                break;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Compare a table key against `name`, folding ASCII uppercase in `name`.
+fn cmp_key(key: &str, name: &str) -> Ordering {
+    let lowered = name.bytes().map(|b| b.to_ascii_lowercase());
+    key.bytes().cmp(lowered)
+}
+
+/// Case-insensitive lookup of an exact name or extension in the icon table.
+fn lookup(name: &str) -> Option<&'static IconEntry> {
+    ICON_TABLE
+        .binary_search_by(|(key, _)| cmp_key(key, name))
+        .ok()
+        .map(|idx| &ICON_TABLE[idx].1)
+}
+
+/// Look up an entry by exact name, then compound extension (e.g. `.tar.gz`),
+/// then simple extension.
+fn lookup_name(name: &str) -> Option<&'static IconEntry> {
+    // Exact filename match
+    if let Some(entry) = lookup(name) {
+        return Some(entry);
+    }
+
+    // Compound extension (e.g., .tar.gz, .spec.ts)
+    if let Some(idx) = name.find('.') {
+        if let Some(idx2) = name[idx + 1..].find('.') {
+            if let Some(entry) = lookup(&name[idx + 1 + idx2..]) {
+                return Some(entry);
+            }
+        }
+    }
+
+    // Simple extension
+    name.rfind('.').and_then(|dot_pos| lookup(&name[dot_pos..]))
+}
+
+/// Whether a regular file's icon depends on its execute permission (so
+/// listing it needs a stat()): true when no name or extension icon matches,
+/// or, outside the Nerd Font sets, when its extension has no category.
+pub fn depends_on_mode(name: &str) -> bool {
+    lookup_name(name).is_none()
+        || (!matches!(icon_set(), IconSet::NerdV3 | IconSet::NerdV2)
+            && extension_kind(name) == Kind::File)
 }
 
-/// Look up the icon for a file by name, extension, and file type.
+/// Look up the icon for a file by name, extension, and file type, in the
+/// icon set chosen with `--icons`.
 /// `name` is the file name (just the filename, not the full path).
 /// `is_dir` indicates whether the entry is a directory.
 /// `is_hidden` indicates whether the entry starts with '.'.
 /// `is_symlink` indicates whether the entry is a symbolic link.
 /// `is_executable` indicates whether the entry has execute permission.
 #[allow(clippy::too_many_arguments)]
-pub fn get_icon<'a>(
-    icon_map: &'a HashMap<&str, IconEntry>,
+pub fn get_icon(
+    name: &str,
+    is_dir: bool,
+    is_hidden: bool,
+    is_symlink: bool,
+    is_executable: bool,
+    is_pipe: bool,
+    is_socket: bool,
+    is_block_device: bool,
+    is_char_device: bool,
+) -> IconEntry {
+    let nerd = *nerd_icon(
+        name,
+        is_dir,
+        is_hidden,
+        is_symlink,
+        is_executable,
+        is_pipe,
+        is_socket,
+        is_block_device,
+        is_char_device,
+    );
+
+    let set = icon_set();
+    if set == IconSet::NerdV3 {
+        return nerd;
+    }
+    if set == IconSet::NerdV2 {
+        return IconEntry::new(legacy_glyph(nerd.icon), nerd.color);
+    }
+
+    let kind = if is_pipe {
+        Kind::Pipe
+    } else if is_socket {
+        Kind::Socket
+    } else if is_block_device || is_char_device {
+        Kind::Device
+    } else if is_dir {
+        Kind::Dir
+    } else if is_symlink {
+        Kind::Symlink
+    } else {
+        match extension_kind(name) {
+            Kind::File if is_executable => Kind::Exec,
+            kind => kind,
+        }
+    };
+    IconEntry::new(kind.glyph(set), nerd.color)
+}
+
+/// Which glyphs icons are drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IconSet {
+    /// Nerd Fonts 3.x (the default)
+    NerdV3,
+    /// Nerd Fonts 2.x, whose Material Design codepoints were moved in 3.0
+    NerdV2,
+    Emoji,
+    /// Single ASCII characters, for fonts without icons
+    Ascii,
+    /// Bracketed words such as `[dir]`, readable by screen readers
+    Text,
+}
+
+static ICON_SET: OnceLock<IconSet> = OnceLock::new();
+
+fn icon_set() -> IconSet {
+    *ICON_SET.get_or_init(|| IconSet::NerdV3)
+}
+
+/// Names accepted by `--icons` and LSF_ICONS, besides `auto`.
+pub const SET_NAMES: [&str; 5] = ["nerd-v3", "nerd-v2", "emoji", "ascii", "text"];
+
+/// Select the icon set from `--icons` (or LSF_ICONS, applied by the config).
+/// `auto` is a heuristic: there is no way to ask a terminal which glyphs its
+/// font has, so Nerd Font glyphs are used unless the terminal is the Linux
+/// console or the locale's charset is not UTF-8, where they cannot render
+/// and ASCII is used instead.
+pub fn init(args: &Args) {
+    let parse = |name: &str| match name {
+        "nerd-v3" => Some(IconSet::NerdV3),
+        "nerd-v2" => Some(IconSet::NerdV2),
+        "emoji" => Some(IconSet::Emoji),
+        "ascii" => Some(IconSet::Ascii),
+        "text" => Some(IconSet::Text),
+        _ => None,
+    };
+
+    let set = match args.icons.as_deref() {
+        Some(name) if name != "auto" => parse(name),
+        _ => None,
+    }
+    .unwrap_or_else(|| {
+        auto_set(
+            std::env::var("TERM").ok().as_deref(),
+            ctype_locale().as_deref(),
+        )
+    });
+    let _ = ICON_SET.set(set);
+
+    let layout = match args.icon_width.as_str() {
+        "1" => IconLayout {
+            cell_width: Some(1),
+            fill: args.icon_padding != "none",
+        },
+        "2" => IconLayout {
+            cell_width: Some(2),
+            fill: args.icon_padding != "none",
+        },
+        // auto: if the terminal advances the cursor by two cells for a Nerd
+        // Font glyph, measure icons that way; otherwise trust Unicode widths
+        _ => IconLayout {
+            cell_width: matches!(set, IconSet::NerdV3 | IconSet::NerdV2)
+                .then(|| probe_glyph_width(DEFAULT_ICON.icon))
+                .flatten()
+                .filter(|&width| width == 2),
+            fill: false,
+        },
+    };
+    let _ = ICON_LAYOUT.set(layout);
+}
+
+/// Ask the terminal how many cells it advances the cursor for `glyph`, by
+/// printing it and requesting a cursor position report. Only done when
+/// stdin and stdout are both the terminal (so nothing lands in a pipe), and
+/// the line is erased afterwards. None if the terminal does not answer
+/// within 200ms.
+fn probe_glyph_width(glyph: &str) -> Option<usize> {
+    use std::io::{Read, Write};
+    use std::os::unix::io::AsRawFd;
+
+    // SAFETY: isatty only inspects the descriptors.
+    if unsafe { libc::isatty(0) != 1 || libc::isatty(1) != 1 } {
+        return None;
+    }
+    let mut tty = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    let fd = tty.as_raw_fd();
+
+    // Read the reply byte by byte, without echoing it
+    // SAFETY: termios is plain data filled in by tcgetattr.
+    let mut saved: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(fd, &mut saved) } != 0 {
+        return None;
+    }
+    let mut raw = saved;
+    raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+    raw.c_cc[libc::VMIN] = 1;
+    raw.c_cc[libc::VTIME] = 0;
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) } != 0 {
+        return None;
+    }
+
+    let mut query = || -> Option<usize> {
+        write!(tty, "\r{}\x1b[6n", glyph).ok()?;
+        tty.flush().ok()?;
+        // The reply is "ESC [ row ; column R"
+        let mut reply = Vec::new();
+        while !reply.ends_with(b"R") && reply.len() < 32 {
+            let mut poll = libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            if unsafe { libc::poll(&mut poll, 1, 200) } <= 0 {
+                return None;
+            }
+            let mut byte = [0u8; 1];
+            if tty.read(&mut byte).ok()? == 0 {
+                return None;
+            }
+            reply.push(byte[0]);
+        }
+        let reply = std::str::from_utf8(&reply).ok()?;
+        let (_, column) = reply.strip_suffix('R')?.rsplit_once(';')?;
+        column.parse::<usize>().ok()?.checked_sub(1)
+    };
+    let width = query();
+
+    let _ = write!(tty, "\r\x1b[K");
+    let _ = tty.flush();
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &saved) };
+    width
+}
+
+/// How many terminal cells an icon takes and how that is achieved.
+#[derive(Debug, Clone, Copy)]
+struct IconLayout {
+    /// Cells per icon (`--icon-width`, or 2 when `auto` finds the terminal
+    /// advances two cells per glyph); None trusts the Unicode width
+    cell_width: Option<usize>,
+    /// Pad icons with spaces up to the cell width, for terminals that draw
+    /// wide glyphs over the following cell; otherwise the terminal is assumed
+    /// to advance by the cell width
+    fill: bool,
+}
+
+static ICON_LAYOUT: OnceLock<IconLayout> = OnceLock::new();
+
+fn icon_layout() -> IconLayout {
+    *ICON_LAYOUT.get_or_init(|| IconLayout {
+        cell_width: None,
+        fill: true,
+    })
+}
+
+/// Whether an icon is a single character in a Unicode private use area,
+/// where Nerd Font glyphs live.
+fn is_private_use_glyph(icon: &str) -> bool {
+    let mut chars = icon.chars();
+    matches!(
+        (chars.next(), chars.next()),
+        (
+            Some('\u{e000}'..='\u{f8ff}' | '\u{f0000}'..='\u{ffffd}' | '\u{100000}'..='\u{10fffd}'),
+            None,
+        )
+    )
+}
+
+/// The icon set `--icons=auto` picks: ASCII on the Linux console, whose font
+/// has no Nerd Font glyphs, and with non-UTF-8 locales; Nerd Fonts 3.x
+/// otherwise. Whether the terminal font has the glyphs cannot be detected.
+fn auto_set(term: Option<&str>, locale: Option<&str>) -> IconSet {
+    if term == Some("linux") || !charset_is_utf8(locale) {
+        IconSet::Ascii
+    } else {
+        IconSet::NerdV3
+    }
+}
+
+/// The locale governing the character set, from the first non-empty of
+/// `LC_ALL`, `LC_CTYPE` and `LANG`.
+fn ctype_locale() -> Option<String> {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|v| !v.is_empty())
+}
+
+/// Whether the locale's charset is UTF-8. An unset locale or one without an
+/// explicit charset (e.g. `C`) counts as UTF-8, since terminals default to it.
+fn charset_is_utf8(locale: Option<&str>) -> bool {
+    let charset = match locale.and_then(|l| l.split_once('.')) {
+        Some((_, rest)) => rest.split('@').next().unwrap_or(rest).to_ascii_lowercase(),
+        None => return true,
+    };
+    charset == "utf-8" || charset == "utf8"
+}
+
+/// The Nerd Fonts 2.x codepoint for glyphs that moved in 3.0.
+fn legacy_glyph(icon: &'static str) -> &'static str {
+    match icon {
+        "\u{f07e5}" => "\u{f731}", // pipe
+        "\u{f1e6}" => "\u{f6a7}",  // socket
+        "\u{f031b}" => "\u{f81a}", // C#
+        "\u{f0fc3}" => "\u{f718}", // license
+        "\u{f072b}" => "\u{f72b}", // webpack
+        _ => icon,
+    }
+}
+
+/// Broad file categories used by the emoji, ASCII and text icon sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Dir,
+    Symlink,
+    Exec,
+    Pipe,
+    Socket,
+    Device,
+    Image,
+    Audio,
+    Video,
+    Archive,
+    Code,
+    Document,
+    Config,
+    File,
+}
+
+impl Kind {
+    fn glyph(self, set: IconSet) -> &'static str {
+        // Text labels are padded to a common width to keep columns aligned
+        match (self, set) {
+            (Kind::Dir, IconSet::Emoji) => "\u{1f4c1}",
+            (Kind::Dir, IconSet::Ascii) => "/",
+            (Kind::Dir, _) => "[dir]    ",
+            (Kind::Symlink, IconSet::Emoji) => "\u{1f517}",
+            (Kind::Symlink, IconSet::Ascii) => "@",
+            (Kind::Symlink, _) => "[link]   ",
+            (Kind::Exec, IconSet::Emoji) => "\u{1f680}",
+            (Kind::Exec, IconSet::Ascii) => "*",
+            (Kind::Exec, _) => "[exec]   ",
+            (Kind::Pipe, IconSet::Emoji) => "\u{1f500}",
+            (Kind::Pipe, IconSet::Ascii) => "|",
+            (Kind::Pipe, _) => "[pipe]   ",
+            (Kind::Socket, IconSet::Emoji) => "\u{1f50c}",
+            (Kind::Socket, IconSet::Ascii) => "=",
+            (Kind::Socket, _) => "[socket] ",
+            (Kind::Device, IconSet::Emoji) => "\u{1f4bd}",
+            (Kind::Device, IconSet::Ascii) => "#",
+            (Kind::Device, _) => "[device] ",
+            (Kind::Image, IconSet::Emoji) => "\u{1f3a8}",
+            (Kind::Image, IconSet::Ascii) => "i",
+            (Kind::Image, _) => "[image]  ",
+            (Kind::Audio, IconSet::Emoji) => "\u{1f3b5}",
+            (Kind::Audio, IconSet::Ascii) => "a",
+            (Kind::Audio, _) => "[audio]  ",
+            (Kind::Video, IconSet::Emoji) => "\u{1f3ac}",
+            (Kind::Video, IconSet::Ascii) => "v",
+            (Kind::Video, _) => "[video]  ",
+            (Kind::Archive, IconSet::Emoji) => "\u{1f4e6}",
+            (Kind::Archive, IconSet::Ascii) => "z",
+            (Kind::Archive, _) => "[archive]",
+            (Kind::Code, IconSet::Emoji) => "\u{1f4bb}",
+            (Kind::Code, IconSet::Ascii) => "c",
+            (Kind::Code, _) => "[code]   ",
+            (Kind::Document, IconSet::Emoji) => "\u{1f4dd}",
+            (Kind::Document, IconSet::Ascii) => "t",
+            (Kind::Document, _) => "[doc]    ",
+            (Kind::Config, IconSet::Emoji) => "\u{1f527}",
+            (Kind::Config, IconSet::Ascii) => "%",
+            (Kind::Config, _) => "[config] ",
+            (Kind::File, IconSet::Emoji) => "\u{1f4c4}",
+            (Kind::File, IconSet::Ascii) => "-",
+            (Kind::File, _) => "[file]   ",
+        }
+    }
+}
+
+/// Categorize a file by its extension. A bare extension (`.png`, as passed
+/// for sniffed files) counts as one.
+fn extension_kind(name: &str) -> Kind {
+    let ext = match name.rfind('.') {
+        Some(pos) => name[pos + 1..].to_ascii_lowercase(),
+        None => return Kind::File,
+    };
+    match ext.as_str() {
+        "png" | "jpg" | "jpeg" | "gif" | "svg" | "webp" | "bmp" | "ico" | "tiff" | "tif"
+        | "psd" | "heic" | "avif" | "raw" => Kind::Image,
+        "mp3" | "wav" | "flac" | "aac" | "ogg" | "m4a" | "wma" | "opus" | "aiff" | "ape" => {
+            Kind::Audio
+        }
+        "mp4" | "mov" | "avi" | "mkv" | "webm" | "flv" | "wmv" | "m4v" | "mpg" | "mpeg" | "3gp" => {
+            Kind::Video
+        }
+        "zip" | "tar" | "gz" | "bz2" | "xz" | "7z" | "rar" | "tgz" | "tbz2" | "txz" | "zst"
+        | "deb" | "rpm" | "iso" | "dmg" | "jar" => Kind::Archive,
+        "rs" | "c" | "h" | "cpp" | "hpp" | "cc" | "py" | "js" | "mjs" | "ts" | "jsx" | "tsx"
+        | "go" | "java" | "kt" | "rb" | "php" | "swift" | "cs" | "sh" | "bash" | "zsh" | "fish"
+        | "lua" | "pl" | "r" | "scala" | "hs" | "ex" | "exs" | "erl" | "clj" | "dart" | "vue"
+        | "svelte" | "html" | "css" | "scss" | "sql" => Kind::Code,
+        "md" | "txt" | "pdf" | "doc" | "docx" | "odt" | "rtf" | "tex" | "org" | "adoc" | "rst"
+        | "epub" => Kind::Document,
+        "json" | "yaml" | "yml" | "toml" | "ini" | "conf" | "cfg" | "xml" | "env" | "lock" => {
+            Kind::Config
+        }
+        _ => Kind::File,
+    }
+}
+
+/// The Nerd Font (v3) icon for a file by name, extension, and file type.
+#[allow(clippy::too_many_arguments)]
+fn nerd_icon(
     name: &str,
     is_dir: bool,
     is_hidden: bool,
@@ -542,7 +913,7 @@ pub fn get_icon<'a>(
     is_socket: bool,
     is_block_device: bool,
     is_char_device: bool,
-) -> &'a IconEntry {
+) -> &'static IconEntry {
     // Special file types first
     if is_pipe {
         return &PIPE_ICON;
@@ -560,8 +931,7 @@ pub fn get_icon<'a>(
     // Directories
     if is_dir {
         // Check for special directory names
-        let lower = name.to_lowercase();
-        if let Some(entry) = icon_map.get(lower.as_str()) {
+        if let Some(entry) = lookup(name) {
             return entry;
         }
         if is_hidden {
@@ -570,61 +940,75 @@ pub fn get_icon<'a>(
         return &DIR_ICON;
     }
 
+    // Regular files and symlinks: exact filename, then compound extension, then extension
+    if let Some(entry) = lookup_name(name) {
+        return entry;
+    }
+
     // Symlinks (show the symlink icon if it can't be resolved to a type)
     if is_symlink {
-        // Still try to match by name/extension, fall back to symlink icon
-        let lower = name.to_lowercase();
-        if let Some(entry) = icon_map.get(lower.as_str()) {
-            return entry;
-        }
-        // Try compound extensions (e.g., .tar.gz)
-        if let Some(idx) = lower.find('.') {
-            if let Some(idx2) = lower[idx + 1..].find('.') {
-                let compound = &lower[idx + 1 + idx2..];
-                if let Some(entry) = icon_map.get(compound) {
-                    return entry;
-                }
-            }
-        }
-        if let Some(dot_pos) = lower.rfind('.') {
-            let ext = &lower[dot_pos..];
-            if let Some(entry) = icon_map.get(ext) {
-                return entry;
-            }
-        }
         return &SYMLINK_ICON;
     }
 
-    // Regular files: check exact filename, then compound extension, then extension
-    let lower = name.to_lowercase();
-
-    // Exact filename match
-    if let Some(entry) = icon_map.get(lower.as_str()) {
-        return entry;
+    // Executable files
+    if is_executable {
+        return &EXEC_ICON;
     }
 
-    // Compound extension (e.g., .tar.gz, .spec.ts)
-    if let Some(idx) = lower.find('.') {
-        if let Some(idx2) = lower[idx + 1..].find('.') {
-            let compound_ext = &lower[idx + 1 + idx2..];
-            if let Some(entry) = icon_map.get(compound_ext) {
-                return entry;
-            }
-        }
+    &DEFAULT_ICON
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn private_use_glyphs() {
+        assert!(is_private_use_glyph("\u{e5ff}"));
+        assert!(is_private_use_glyph("\u{f07e5}"));
+        assert!(is_private_use_glyph("\u{10fffd}"));
+        assert!(!is_private_use_glyph("d"));
+        assert!(!is_private_use_glyph("📁"));
+        assert!(!is_private_use_glyph("\u{e5ff}\u{e5ff}"));
+        assert!(!is_private_use_glyph(""));
     }
 
-    // Simple extension
-    if let Some(dot_pos) = lower.rfind('.') {
-        let ext = &lower[dot_pos..];
-        if let Some(entry) = icon_map.get(ext) {
-            return entry;
+    #[test]
+    fn auto_icon_set() {
+        let cases = [
+            (Some("xterm-256color"), Some("en_US.UTF-8"), IconSet::NerdV3),
+            (
+                Some("xterm-256color"),
+                Some("de_DE.utf8@euro"),
+                IconSet::NerdV3,
+            ),
+            (Some("xterm-256color"), Some("C"), IconSet::NerdV3),
+            (None, None, IconSet::NerdV3),
+            (Some("linux"), Some("en_US.UTF-8"), IconSet::Ascii),
+            (Some("xterm"), Some("en_US.ISO-8859-1"), IconSet::Ascii),
+            (Some("xterm"), Some("ja_JP.eucJP"), IconSet::Ascii),
+        ];
+        for (term, locale, expected) in cases {
+            assert_eq!(auto_set(term, locale), expected, "{:?} {:?}", term, locale);
         }
     }
 
-    // Executable files
-    if is_executable {
-        return &EXEC_ICON;
+    #[test]
+    fn legacy_glyphs() {
+        assert_eq!(legacy_glyph("\u{f07e5}"), "\u{f731}");
+        assert_eq!(legacy_glyph("\u{f1e6}"), "\u{f6a7}");
+        assert_eq!(legacy_glyph("\u{f0fc3}"), "\u{f718}");
+        // Glyphs that did not move are kept
+        assert_eq!(legacy_glyph("\u{e5ff}"), "\u{e5ff}");
+        // Every Nerd Fonts 3.x icon maps to a single glyph in the 2.x range
+        for icon in [
+            "\u{f07e5}",
+            "\u{f1e6}",
+            "\u{f031b}",
+            "\u{f0fc3}",
+            "\u{f072b}",
+        ] {
+            assert!(legacy_glyph(icon) < "\u{f0000}", "{:?}", icon);
+        }
     }
-
-    &DEFAULT_ICON
 }
@@ -0,0 +1,242 @@
+use std::ffi::{CStr, CString};
+use std::sync::OnceLock;
+
+use crate::cli::Args;
+
+/// How file names are compared when sorting by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Collation {
+    /// Plain byte order, as in the C/POSIX locale
+    Bytes,
+    /// The C library's collation for LC_COLLATE (strcoll/strxfrm)
+    Locale,
+    /// Natural order: digit runs compare numerically
+    Natural,
+    /// Case-insensitive order (used when no locale is configured)
+    Folded,
+}
+
+impl Collation {
+    /// Pick the collation from `--collate`, falling back to the environment.
+    /// This initializes LC_COLLATE from the environment when it is needed.
+    fn select(args: &Args) -> Self {
+        match args.collate.as_deref() {
+            Some("c") => Collation::Bytes,
+            Some("natural") => Collation::Natural,
+            Some(_) => match init_collate_locale() {
+                Some(true) => Collation::Bytes,
+                _ => Collation::Locale,
+            },
+            None => {
+                if locale_env("LC_COLLATE").is_none() {
+                    return Collation::Folded;
+                }
+                match init_collate_locale() {
+                    Some(true) => Collation::Bytes,
+                    Some(false) => Collation::Locale,
+                    // The configured locale is not installed
+                    None => Collation::Folded,
+                }
+            }
+        }
+    }
+}
+
+static COLLATION: OnceLock<Collation> = OnceLock::new();
+static TIME_IS_C: OnceLock<bool> = OnceLock::new();
+
+/// Set up the locale categories lsf uses, once, from the main thread before
+/// anything is listed.
+pub fn init(args: &Args) {
+    let _ = COLLATION.set(Collation::select(args));
+    let _ = TIME_IS_C.set(init_time_locale());
+}
+
+/// The collation picked by `init` (case-folded if it was never called).
+pub fn collation() -> Collation {
+    COLLATION.get().copied().unwrap_or(Collation::Folded)
+}
+
+/// The value of the environment variable that decides `category`, following
+/// POSIX precedence: LC_ALL, then the category itself, then LANG.
+pub fn locale_env(category: &str) -> Option<String> {
+    ["LC_ALL", category, "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|v| !v.is_empty())
+}
+
+/// Set LC_COLLATE from the environment. Returns whether the resulting locale
+/// is C/POSIX, or None if the requested locale could not be loaded.
+fn init_collate_locale() -> Option<bool> {
+    // SAFETY: only called once, by `init` on the main thread before any other
+    // locale-dependent C calls; the returned string is copied before
+    // setlocale is called again.
+    let name = unsafe {
+        let ptr = libc::setlocale(libc::LC_COLLATE, b"\0".as_ptr().cast());
+        if ptr.is_null() {
+            return None;
+        }
+        CStr::from_ptr(ptr).to_string_lossy().into_owned()
+    };
+    Some(name == "C" || name == "POSIX")
+}
+
+/// Transform a name with strxfrm so that comparing the results byte-wise
+/// matches strcoll under the current LC_COLLATE.
+pub fn collation_key(name: &str) -> Vec<u8> {
+    let src = match CString::new(name) {
+        Ok(s) => s,
+        Err(_) => return name.as_bytes().to_vec(),
+    };
+
+    let mut buf: Vec<u8> = vec![0; name.len() * 4 + 1];
+    loop {
+        // SAFETY: buf is valid for buf.len() bytes and src is NUL-terminated.
+        let needed = unsafe { libc::strxfrm(buf.as_mut_ptr().cast(), src.as_ptr(), buf.len()) };
+        if needed < buf.len() {
+            buf.truncate(needed);
+            return buf;
+        }
+        buf.resize(needed + 1, 0);
+    }
+}
+
+/// Set LC_TIME from the environment. Returns whether the resulting locale is
+/// C/POSIX (or could not be loaded, in which case C stays in effect).
+fn init_time_locale() -> bool {
+    if locale_env("LC_TIME").is_none() {
+        return true;
+    }
+    // SAFETY: only called once, by `init`, as for LC_COLLATE above.
+    let name = unsafe {
+        let ptr = libc::setlocale(libc::LC_TIME, b"\0".as_ptr().cast());
+        if ptr.is_null() {
+            return true;
+        }
+        CStr::from_ptr(ptr).to_string_lossy().into_owned()
+    };
+    name == "C" || name == "POSIX"
+}
+
+/// Whether LC_TIME is the C/POSIX locale (also if `init` was never called).
+pub fn time_is_c() -> bool {
+    TIME_IS_C.get().copied().unwrap_or(true)
+}
+
+/// Whether the LC_TIME locale writes the day before the month, judging by
+/// its preferred date representation (`nl_langinfo(D_FMT)`).
+pub fn day_before_month() -> bool {
+    let d_fmt = unsafe {
+        let ptr = libc::nl_langinfo(libc::D_FMT);
+        if ptr.is_null() {
+            return false;
+        }
+        CStr::from_ptr(ptr).to_string_lossy().into_owned()
+    };
+    day_first(&d_fmt)
+}
+
+/// Whether a strftime date format has a day conversion before any month
+/// conversion, e.g. `%d.%m.%Y` but not `%m/%d/%y` or `%Y-%m-%d`.
+fn day_first(d_fmt: &str) -> bool {
+    let mut chars = d_fmt.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        // Skip E and O modifiers, as in %Ey or %Od
+        let conv = chars.find(|&c| c != 'E' && c != 'O');
+        match conv {
+            Some('d' | 'e') => return true,
+            Some('m' | 'b' | 'B' | 'h' | 'D' | 'F') => return false,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Break a Unix timestamp down into local calendar time.
+pub fn localtime(secs: i64) -> libc::tm {
+    let t = secs as libc::time_t;
+    unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&t, &mut tm);
+        tm
+    }
+}
+
+/// Format a broken-down time with the C library's strftime, so that month
+/// and day names follow LC_TIME.
+pub fn strftime(fmt: &str, tm: &libc::tm) -> String {
+    if fmt.is_empty() {
+        return String::new();
+    }
+    let cfmt = match CString::new(fmt) {
+        Ok(s) => s,
+        Err(_) => return String::new(),
+    };
+
+    let mut buf: Vec<u8> = vec![0; 64 + fmt.len() * 4];
+    loop {
+        let n = unsafe { libc::strftime(buf.as_mut_ptr().cast(), buf.len(), cfmt.as_ptr(), tm) };
+        // strftime returns 0 both for an empty result and for a too-small buffer
+        if n > 0 || buf.len() > 4096 {
+            buf.truncate(n);
+            return String::from_utf8_lossy(&buf).into_owned();
+        }
+        buf.resize(buf.len() * 4, 0);
+    }
+}
+
+/// The twelve abbreviated month names of the current LC_TIME, padded with
+/// spaces to a common display width so long-view columns stay aligned.
+pub fn padded_months() -> Vec<String> {
+    let names: Vec<String> = (0..12)
+        .map(|mon| {
+            let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+            tm.tm_mon = mon;
+            tm.tm_mday = 1;
+            tm.tm_year = 100;
+            strftime("%b", &tm)
+        })
+        .collect();
+
+    let width = names
+        .iter()
+        .map(|n| unicode_width::UnicodeWidthStr::width(n.as_str()))
+        .max()
+        .unwrap_or(0);
+    names
+        .into_iter()
+        .map(|n| {
+            let pad = width - unicode_width::UnicodeWidthStr::width(n.as_str());
+            format!("{}{}", n, " ".repeat(pad))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::day_first;
+
+    #[test]
+    fn date_order() {
+        let cases = [
+            ("%m/%d/%y", false),
+            ("%D", false),
+            ("%Y-%m-%d", false),
+            ("%F", false),
+            ("%d.%m.%Y", true),
+            ("%d/%m/%Y", true),
+            ("%e %B %Y", true),
+            ("%Y\u{5e74}%m\u{6708}%d\u{65e5}", false),
+            ("%%d %m", false),
+            ("%Od/%Om/%Ey", true),
+            ("", false),
+        ];
+        for (fmt, expected) in cases {
+            assert_eq!(day_first(fmt), expected, "day_first({:?})", fmt);
+        }
+    }
+}
@@ -1,10 +1,19 @@
 mod cli;
+mod config;
 mod entry;
+mod fileattr;
+mod filter;
 mod format;
 mod git;
 mod grid;
 mod icons;
+mod locale;
 mod output;
+mod sniff;
+mod stat;
+mod summary;
+mod theme;
+mod xattr;
 
 use std::io::{self, BufWriter, Write};
 use std::path::Path;
@@ -13,7 +22,7 @@ use std::process;
 use clap::Parser;
 
 fn main() {
-    let args = cli::Args::parse();
+    let mut args = cli::Args::parse();
 
     // Handle custom help/version flags
     if args.help {
@@ -28,15 +37,19 @@ fn main() {
         return;
     }
 
+    config::apply(&mut args);
+    locale::init(&args);
+    theme::init(&args);
+    icons::init(&args);
+    if let Err(e) = filter::init(&args) {
+        eprintln!("lsf: {}", e);
+        process::exit(2);
+    }
+
     // Handle tree mode separately
     if args.tree {
         let stdout = io::stdout();
         let mut out = BufWriter::new(stdout.lock());
-        let icon_map = if args.no_icons {
-            std::collections::HashMap::new()
-        } else {
-            icons::build_icon_map()
-        };
         let use_color = args.use_color();
         let show_icons = !args.no_icons;
 
@@ -51,26 +64,42 @@ fn main() {
             }
 
             // Load git status for tree view
-            let git_repo = if args.git {
+            let git_repo = if args.show_git() {
                 git::load_git_status(path)
             } else {
                 None
             };
 
+            // With --audit or filters, read and prune the whole tree before
+            // printing it
+            let pruned = output::is_filtering(&args)
+                .then(|| output::prune_tree(path, &args, git_repo.as_ref()));
+
+            let mut summary = summary::Summary::default();
             if let Err(e) = output::print_tree(
                 &mut out,
                 path,
                 &args,
-                &icon_map,
                 use_color,
                 show_icons,
                 "",
                 true,
                 0,
                 git_repo.as_ref(),
+                pruned.as_ref(),
+                &mut summary,
             ) {
                 eprintln!("lsf: {}: {}", path_str, e);
             }
+
+            if let Some(top) = args.summary {
+                let written = writeln!(out).and_then(|_| {
+                    summary.write(&mut out, "Total", top, args.si, use_color, show_icons)
+                });
+                if let Err(e) = written {
+                    eprintln!("lsf: write error: {}", e);
+                }
+            }
         }
 
         if let Err(e) = out.flush() {
@@ -1,25 +1,28 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::io::{self, BufWriter, Write};
-use std::path::Path;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
 
-use crate::cli::Args;
+use crate::cli::{Args, Column};
 use crate::entry::{self, FileEntry};
+use crate::filter;
 use crate::format;
 use crate::git::{self, GitRepo};
 use crate::grid;
-use crate::icons::{self, IconEntry};
+use crate::icons;
+use crate::summary::Summary;
+use crate::theme::{self, Role};
+use crate::xattr;
+
+/// Number of entries read before long-view column widths are fixed when streaming.
+const STREAM_LOOKAHEAD: usize = 1024;
 
 /// Main output driver. Takes parsed args and runs the listing.
 pub fn run(args: &Args) -> io::Result<()> {
     let stdout = io::stdout();
     let mut out = BufWriter::new(stdout.lock());
 
-    let icon_map = if args.no_icons {
-        HashMap::new()
-    } else {
-        icons::build_icon_map()
-    };
-
     let use_color = args.use_color();
     let show_icons = !args.no_icons;
     let term_width = grid::get_terminal_width(args.term_width);
@@ -28,6 +31,9 @@ pub fn run(args: &Args) -> io::Result<()> {
     let paths: Vec<&str> = args.paths.iter().map(|s| s.as_str()).collect();
     let multi = paths.len() > 1;
 
+    // Grand total for --summary with -R
+    let mut total = Summary::default();
+
     // Separate files and directories
     let mut file_entries: Vec<FileEntry> = Vec::new();
     let mut dir_paths: Vec<&str> = Vec::new();
@@ -42,9 +48,25 @@ pub fn run(args: &Args) -> io::Result<()> {
             continue;
         }
 
-        if path.is_file() || path.is_symlink() && !path.is_dir() || args.directory {
+        // Anything but a directory (files, symlinks, devices, fifos, sockets)
+        // is listed as an entry itself
+        if !path.is_dir() || args.directory {
             let follow = args.dereference || args.dereference_command_line;
-            if let Ok(fe) = FileEntry::from_path(path, follow) {
+            if let Ok(mut fe) = FileEntry::from_path(path, follow) {
+                if args.is_long() {
+                    fe.load_xattrs();
+                } else if use_color || args.audit {
+                    fe.load_capabilities();
+                }
+                if args.needs_attributes() {
+                    fe.load_attributes();
+                }
+                if args.sniff_content() {
+                    fe.load_content_type();
+                }
+                if args.dir_count.is_some() {
+                    fe.load_child_count(args);
+                }
                 file_entries.push(fe);
             }
         } else {
@@ -52,10 +74,12 @@ pub fn run(args: &Args) -> io::Result<()> {
         }
     }
 
+    file_entries.retain(filter::matches);
+
     // Print file arguments first
     if !file_entries.is_empty() {
         // Apply git status to file entries
-        if args.git {
+        if args.show_git() {
             let cwd = std::env::current_dir().unwrap_or_else(|_| Path::new(".").to_path_buf());
             if let Some(git_repo) = git::load_git_status(&cwd) {
                 entry::apply_git_status(&mut file_entries, &git_repo);
@@ -63,11 +87,13 @@ pub fn run(args: &Args) -> io::Result<()> {
         }
 
         entry::sort_entries(&mut file_entries, args);
+        if args.summary.is_some() {
+            file_entries.iter().for_each(|e| total.add(e));
+        }
         print_entries(
             &mut out,
             &file_entries,
             args,
-            &icon_map,
             use_color,
             show_icons,
             term_width,
@@ -79,7 +105,7 @@ pub fn run(args: &Args) -> io::Result<()> {
     }
 
     // Print directories
-    let show_header = multi || !file_entries.is_empty() || args.recursive;
+    let show_header = multi || !file_entries.is_empty() || args.is_recursive();
 
     for (idx, path_str) in dir_paths.iter().enumerate() {
         let path = Path::new(path_str);
@@ -89,19 +115,45 @@ pub fn run(args: &Args) -> io::Result<()> {
                 writeln!(out)?;
             }
             if use_color {
-                writeln!(out, "\x1b[1;34m{}:\x1b[0m", path_str)?;
+                writeln!(
+                    out,
+                    "{}",
+                    theme::paint(Role::DirHeader, &format!("{}:", path_str))
+                )?;
             } else {
                 writeln!(out, "{}:", path_str)?;
             }
         }
 
         // Load git status for this directory
-        let git_repo = if args.git {
+        let git_repo = if args.show_git() {
             git::load_git_status(path)
         } else {
             None
         };
 
+        if can_stream(args) {
+            let mut summary = Summary::default();
+            match entry::DirEntries::open(path, args) {
+                Ok(entries) => stream_entries(
+                    &mut out,
+                    entries,
+                    args,
+                    use_color,
+                    show_icons,
+                    term_width,
+                    terminator,
+                    git_repo.as_ref(),
+                    &mut summary,
+                )?,
+                Err(e) => eprintln!("lsf: cannot open directory '{}': {}", path_str, e),
+            }
+            if let Some(top) = args.summary {
+                summary.write(&mut out, "Summary", top, args.si, use_color, show_icons)?;
+            }
+            continue;
+        }
+
         match entry::read_directory(path, args) {
             Ok(mut entries) => {
                 // Apply git status
@@ -110,30 +162,25 @@ pub fn run(args: &Args) -> io::Result<()> {
                 }
 
                 entry::sort_entries(&mut entries, args);
+                let shown = display_filter(&entries, args);
 
                 if args.is_long() {
                     // Print total line
-                    let total = format::total_blocks(&entries);
+                    let total = format::total_blocks(&shown);
                     writeln!(out, "total {}", total)?;
                 }
 
                 print_entries(
-                    &mut out, &entries, args, &icon_map, use_color, show_icons, term_width,
-                    terminator,
+                    &mut out, &shown, args, use_color, show_icons, term_width, terminator,
                 )?;
+                write_summary(&mut out, &shown, args, use_color, show_icons, &mut total)?;
 
                 // Recursive subdirectories
-                if args.recursive {
-                    let mut subdirs: Vec<FileEntry> = entries
+                if args.is_recursive() {
+                    // Descend in the order the entries were listed
+                    let subdirs = entries
                         .iter()
-                        .filter(|e| e.is_dir && e.name != "." && e.name != "..")
-                        .cloned()
-                        .collect();
-                    subdirs.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-
-                    if args.reverse {
-                        subdirs.reverse();
-                    }
+                        .filter(|e| e.is_dir && e.name != "." && e.name != "..");
 
                     for subdir in subdirs {
                         let subpath = subdir.path.to_string_lossy().to_string();
@@ -155,13 +202,13 @@ pub fn run(args: &Args) -> io::Result<()> {
                             &mut out,
                             &subpath,
                             args,
-                            &icon_map,
                             use_color,
                             show_icons,
                             term_width,
                             terminator,
                             path_str,
                             git_repo.as_ref(),
+                            &mut total,
                         )?;
                     }
                 }
@@ -177,17 +224,44 @@ pub fn run(args: &Args) -> io::Result<()> {
         // Already printed above, but without header
     }
 
+    if let Some(top) = args.summary {
+        if args.is_recursive() {
+            writeln!(out)?;
+            total.write(&mut out, "Total", top, args.si, use_color, show_icons)?;
+        }
+    }
+
     out.flush()?;
     Ok(())
 }
 
+/// Print the `--summary` footer for a directory's entries and add them to
+/// the grand total.
+fn write_summary(
+    out: &mut impl Write,
+    entries: &[FileEntry],
+    args: &Args,
+    use_color: bool,
+    show_icons: bool,
+    total: &mut Summary,
+) -> io::Result<()> {
+    let top = match args.summary {
+        Some(top) => top,
+        None => return Ok(()),
+    };
+    let mut summary = Summary::default();
+    entries.iter().for_each(|e| summary.add(e));
+    summary.write(out, "Summary", top, args.si, use_color, show_icons)?;
+    total.merge(&summary);
+    Ok(())
+}
+
 /// Print entries for a single directory (not recursive, just the entries).
 #[allow(clippy::too_many_arguments)]
 fn print_entries(
     out: &mut impl Write,
     entries: &[FileEntry],
     args: &Args,
-    icon_map: &HashMap<&str, IconEntry>,
     use_color: bool,
     show_icons: bool,
     term_width: usize,
@@ -198,18 +272,20 @@ fn print_entries(
     }
 
     if args.is_long() {
-        print_long(out, entries, args, icon_map, use_color, show_icons)?;
+        print_long(out, entries, args, use_color, show_icons)?;
     } else if args.one_per_line {
-        let items = format_items(entries, args, icon_map, use_color, show_icons);
+        // Names are never truncated here so the output stays pipeline-safe
+        let items = format_items(entries, args, use_color, show_icons, None);
         let output = grid::format_single_column(&items, terminator);
         write!(out, "{}", output)?;
     } else if args.comma {
-        let items = format_items(entries, args, icon_map, use_color, show_icons);
+        let items = format_items(entries, args, use_color, show_icons, Some(term_width));
         let output = grid::format_comma(&items, term_width);
         write!(out, "{}", output)?;
     } else {
         // Grid view
-        let items = format_items(entries, args, icon_map, use_color, show_icons);
+        let fit_width = if args.zero { None } else { Some(term_width) };
+        let items = format_items(entries, args, use_color, show_icons, fit_width);
         if args.zero {
             let output = grid::format_single_column(&items, '\0');
             write!(out, "{}", output)?;
@@ -223,18 +299,22 @@ fn print_entries(
 }
 
 /// Format items for grid / one-per-line / comma views.
+/// `fit_width` is the terminal width names may be truncated to fit (see
+/// `--max-name-width`), or None to always print names in full.
 fn format_items(
     entries: &[FileEntry],
     args: &Args,
-    icon_map: &HashMap<&str, IconEntry>,
     use_color: bool,
     show_icons: bool,
-) -> Vec<String> {
+    fit_width: Option<usize>,
+) -> Vec<grid::Item> {
     let show_git = args.git;
     entries
         .iter()
         .map(|entry| {
             let mut item = String::with_capacity(64);
+            // Columns the icon takes beyond its display width
+            let mut icon_extra = 0;
 
             // Inode
             if args.inode {
@@ -242,7 +322,7 @@ fn format_items(
             }
 
             // Blocks
-            if args.show_size {
+            if args.show_size() {
                 let blocks = entry.blocks / 2;
                 item.push_str(&format!("{} ", blocks));
             }
@@ -252,7 +332,7 @@ fn format_items(
                 if let Some(ref status) = entry.git_status {
                     let marker = status.marker(use_color);
                     if !marker.is_empty() {
-                        item.push_str(marker);
+                        item.push_str(&marker);
                         item.push(' ');
                     }
                 }
@@ -261,8 +341,7 @@ fn format_items(
             // Icon
             if show_icons {
                 let icon = icons::get_icon(
-                    icon_map,
-                    &entry.name,
+                    entry.icon_name(),
                     entry.is_dir,
                     entry.is_hidden,
                     entry.is_symlink,
@@ -272,37 +351,57 @@ fn format_items(
                     entry.is_block_device,
                     entry.is_char_device,
                 );
+                let plain = icon.plain();
+                icon_extra = icon.width().saturating_sub(grid::display_width(&plain));
                 if use_color {
                     item.push_str(&icon.colored());
                 } else {
-                    item.push_str(&icon.plain());
+                    item.push_str(&plain);
                 }
                 item.push(' ');
             }
 
-            // Filename (with color)
-            item.push_str(&format::colorize_filename(entry, use_color));
-
             // Classify indicator
+            let mut suffix = String::new();
             if args.classify {
-                item.push_str(format::classify_indicator(entry, true));
+                suffix.push_str(format::classify_indicator(entry, true));
             } else if args.slash_dirs && entry.is_dir {
-                item.push('/');
+                suffix.push('/');
+            }
+
+            // Entry count of directories
+            if args.count_in_name() {
+                suffix.push_str(&format::count_suffix(entry, use_color));
             }
 
             // Symlink target in non-long modes
             if entry.is_symlink && args.one_per_line {
                 if let Some(ref target) = entry.symlink_target {
-                    item.push_str(" -> ");
+                    suffix.push_str(" -> ");
                     if use_color {
-                        item.push_str(&format!("\x1b[36m{}\x1b[0m", target));
+                        suffix.push_str(&theme::paint(Role::SymlinkTarget, target));
                     } else {
-                        item.push_str(target);
+                        suffix.push_str(target);
                     }
                 }
             }
 
-            item
+            // Filename (with color), truncated if requested
+            let limit = fit_width.and_then(|w| {
+                let used = grid::display_width(&item) + icon_extra + grid::display_width(&suffix);
+                args.name_width_limit(w, used)
+            });
+            match limit {
+                Some(max) => {
+                    let name = grid::truncate_middle(&entry.name, max);
+                    item.push_str(&format::colorize_name(entry, &name, use_color));
+                }
+                None => item.push_str(&format::colorize_filename(entry, use_color)),
+            }
+            item.push_str(&suffix);
+
+            let width = grid::display_width(&item) + icon_extra;
+            grid::Item { text: item, width }
         })
         .collect()
 }
@@ -312,93 +411,179 @@ fn print_long(
     out: &mut impl Write,
     entries: &[FileEntry],
     args: &Args,
-    icon_map: &HashMap<&str, IconEntry>,
     use_color: bool,
     show_icons: bool,
 ) -> io::Result<()> {
-    let show_owner = !args.long_no_owner;
-    let show_group = !args.long_no_group && !args.no_group;
-    let show_inode = args.inode;
-    let show_blocks = args.show_size;
-    let show_author = args.author;
-    let human_readable = args.human_readable;
-    let si = args.si;
-    let numeric_ids = args.numeric_uid_gid;
-    let show_git = args.git;
+    let mut printer = LongPrinter::new(entries, args, use_color, show_icons);
+    if args.header && !entries.is_empty() {
+        printer.write_header(out)?;
+    }
+    for entry in entries {
+        printer.write_entry(out, entry)?;
+    }
+    Ok(())
+}
+
+/// Writes long-format lines with column widths measured up front, so the
+/// same widths can be reused for entries that arrive later (streaming).
+struct LongPrinter<'a> {
+    args: &'a Args,
+    show_icons: bool,
+    format: format::LongFormat,
+    user_cache: HashMap<u32, String>,
+    group_cache: HashMap<u32, String>,
+}
 
-    let mut user_cache: HashMap<u32, String> = HashMap::new();
-    let mut group_cache: HashMap<u32, String> = HashMap::new();
+impl<'a> LongPrinter<'a> {
+    /// Measure column widths over `entries`.
+    fn new(entries: &[FileEntry], args: &'a Args, use_color: bool, show_icons: bool) -> Self {
+        let columns = args.long_columns(show_icons);
+        let has = |column: Column| columns.contains(&column);
+        let numeric_ids = args.numeric_uid_gid;
 
-    // Pre-calculate column widths for alignment
-    let nlink_width = entries
-        .iter()
-        .map(|e| e.nlink.to_string().len())
-        .max()
-        .unwrap_or(1);
+        let mut user_cache: HashMap<u32, String> = HashMap::new();
+        let mut group_cache: HashMap<u32, String> = HashMap::new();
+
+        // Pre-calculate column widths for alignment
+        let max_width =
+            |width: &dyn Fn(&FileEntry) -> usize| entries.iter().map(width).max().unwrap_or(1);
+
+        let mut widths = format::ColumnWidths {
+            nlink: max_width(&|e| e.nlink.to_string().len()),
+            ..Default::default()
+        };
+
+        if has(Column::User) || has(Column::Author) {
+            widths.owner = entries
+                .iter()
+                .map(|e| {
+                    if numeric_ids {
+                        e.uid.to_string().len()
+                    } else {
+                        grid::display_width(&entry::get_username(e.uid, &mut user_cache))
+                    }
+                })
+                .max()
+                .unwrap_or(1);
+        }
+
+        if has(Column::Group) {
+            widths.group = entries
+                .iter()
+                .map(|e| {
+                    if numeric_ids {
+                        e.gid.to_string().len()
+                    } else {
+                        grid::display_width(&entry::get_groupname(e.gid, &mut group_cache))
+                    }
+                })
+                .max()
+                .unwrap_or(1);
+        }
 
-    let owner_width = if show_owner {
-        entries
+        // Devices show "major, minor" in the size column
+        let devices: Vec<(u64, u64)> = entries.iter().filter_map(|e| e.device_numbers()).collect();
+        widths.major = devices
             .iter()
-            .map(|e| {
-                if numeric_ids {
-                    e.uid.to_string().len()
-                } else {
-                    entry::get_username(e.uid, &mut user_cache).len()
-                }
-            })
+            .map(|(major, _)| major.to_string().len())
             .max()
-            .unwrap_or(1)
-    } else {
-        0
-    };
-
-    let group_width = if show_group {
-        entries
+            .unwrap_or(0);
+        widths.minor = devices
             .iter()
-            .map(|e| {
-                if numeric_ids {
-                    e.gid.to_string().len()
-                } else {
-                    entry::get_groupname(e.gid, &mut group_cache).len()
-                }
-            })
+            .map(|(_, minor)| minor.to_string().len())
             .max()
-            .unwrap_or(1)
-    } else {
-        0
-    };
-
-    let size_width = entries
-        .iter()
-        .map(|e| format::format_size(e.size, human_readable, si).len())
-        .max()
-        .unwrap_or(1);
+            .unwrap_or(0);
+        let device_width = if devices.is_empty() {
+            0
+        } else {
+            widths.major + 2 + widths.minor
+        };
 
-    let inode_width = if show_inode {
-        entries
+        widths.size = entries
             .iter()
-            .map(|e| e.inode.to_string().len())
+            .filter(|e| e.device_numbers().is_none())
+            .map(|e| format::size_text(e, args.human_readable, args.si, args.count_in_size()).len())
             .max()
             .unwrap_or(1)
-    } else {
-        0
-    };
+            .max(device_width);
+
+        if has(Column::Inode) {
+            widths.inode = max_width(&|e| e.inode.to_string().len());
+        }
+        if has(Column::Blocks) {
+            widths.blocks = max_width(&|e| (e.blocks / 2).to_string().len());
+        }
+        if has(Column::Context) {
+            widths.context = max_width(&|e| format::format_context(e).len());
+        }
+        if has(Column::Caps) {
+            widths.caps = max_width(&|e| format::format_caps(e).len());
+        }
+        if has(Column::Mime) {
+            widths.mime = max_width(&|e| e.mime_type().len());
+        }
+        if has(Column::Git) {
+            widths.git = entries
+                .iter()
+                .filter_map(|e| e.git_status.as_ref().map(|s| s.marker_width()))
+                .max()
+                .unwrap_or(0);
+        }
 
-    let blocks_width = if show_blocks {
-        entries
+        let show_attr_indicator = entries
             .iter()
-            .map(|e| (e.blocks / 2).to_string().len())
-            .max()
-            .unwrap_or(1)
-    } else {
-        0
-    };
+            .any(|e| xattr::indicator(&e.xattrs).is_some());
 
-    for entry in entries {
-        let icon_str = if show_icons {
+        let time_format = format::TimeFormat::from_args(args);
+        if args.header {
+            widths.fit_header(&columns, &args.permissions, &time_format);
+        }
+
+        LongPrinter {
+            args,
+            show_icons: has(Column::Icon),
+            format: format::LongFormat {
+                columns,
+                perm_style: args.permissions.clone(),
+                show_attr_indicator,
+                human_readable: args.human_readable,
+                si: args.si,
+                numeric_ids,
+                classify: args.classify,
+                slash_dirs: args.slash_dirs,
+                use_color,
+                time_format,
+                count_dirs: args.count_in_size(),
+                heat: format::HeatScale::from_args(entries, args),
+                widths,
+            },
+            user_cache,
+            group_cache,
+        }
+    }
+
+    /// Write the `--header` line naming the columns.
+    fn write_header(&self, out: &mut impl Write) -> io::Result<()> {
+        let icon_width = if self.show_icons {
+            let icon = icons::get_icon("", false, false, false, false, false, false, false, false);
+            icon.width()
+        } else {
+            0
+        };
+        writeln!(
+            out,
+            "{}",
+            format::format_long_header(&self.format, icon_width)
+        )
+    }
+
+    /// Write the long-format line for a single entry.
+    fn write_entry(&mut self, out: &mut impl Write, entry: &FileEntry) -> io::Result<()> {
+        let args = self.args;
+
+        let icon_str = if self.show_icons {
             let icon = icons::get_icon(
-                icon_map,
-                &entry.name,
+                entry.icon_name(),
                 entry.is_dir,
                 entry.is_hidden,
                 entry.is_symlink,
@@ -408,7 +593,7 @@ fn print_long(
                 entry.is_block_device,
                 entry.is_char_device,
             );
-            if use_color {
+            if self.format.use_color {
                 icon.colored()
             } else {
                 icon.plain()
@@ -417,50 +602,166 @@ fn print_long(
             String::new()
         };
 
-        let mut line = format::format_long_entry(
+        let line = format::format_long_entry(
             entry,
             &icon_str,
-            show_icons,
-            show_owner,
-            show_group,
-            show_inode,
-            show_blocks,
-            show_author,
-            human_readable,
-            si,
-            numeric_ids,
-            use_color,
-            &mut user_cache,
-            &mut group_cache,
-            nlink_width,
-            owner_width,
-            group_width,
-            size_width,
-            inode_width,
-            blocks_width,
+            &self.format,
+            &mut self.user_cache,
+            &mut self.group_cache,
         );
 
-        // Classify indicator
-        if args.classify {
-            line.push_str(format::classify_indicator(entry, true));
-        } else if args.slash_dirs && entry.is_dir {
-            line.push('/');
+        writeln!(out, "{}", line)?;
+
+        if let Some(ref detail) = args.xattr {
+            self.write_xattrs(out, entry, detail)?;
+        }
+        if args.acl {
+            self.write_acl(out, entry)?;
         }
+        Ok(())
+    }
 
-        // Git status marker at the end of the line
-        if show_git {
-            if let Some(ref status) = entry.git_status {
-                let marker = status.marker(use_color);
-                if !marker.is_empty() {
-                    line.push(' ');
-                    line.push_str(marker);
-                }
+    /// List extended attributes beneath an entry (`--xattr`).
+    fn write_xattrs(
+        &self,
+        out: &mut impl Write,
+        entry: &FileEntry,
+        detail: &str,
+    ) -> io::Result<()> {
+        for name in &entry.xattrs {
+            if detail == "names" {
+                writeln!(out, "\t{}", name)?;
+                continue;
+            }
+            let value = xattr::get(&entry.path, name).unwrap_or_default();
+            if detail == "values" {
+                writeln!(
+                    out,
+                    "\t{}\t{:>6}\t{}",
+                    name,
+                    value.len(),
+                    xattr::format_value(&value)
+                )?;
+            } else {
+                writeln!(out, "\t{}\t{:>6}", name, value.len())?;
             }
         }
+        Ok(())
+    }
 
-        writeln!(out, "{}", line)?;
+    /// List decoded POSIX ACL entries beneath an entry (`--acl`).
+    fn write_acl(&mut self, out: &mut impl Write, entry: &FileEntry) -> io::Result<()> {
+        for (attr, prefix) in [(xattr::ACL_ACCESS, ""), (xattr::ACL_DEFAULT, "default:")] {
+            if !entry.xattrs.iter().any(|n| n == attr) {
+                continue;
+            }
+            let decoded = xattr::get(&entry.path, attr).and_then(|value| {
+                xattr::decode_acl(&value, prefix, &mut self.user_cache, &mut self.group_cache)
+            });
+            for line in decoded.unwrap_or_default() {
+                writeln!(out, "\t{}", line)?;
+            }
+        }
+        Ok(())
     }
+}
+
+/// Whether directory listings can be printed while they are read, rather
+/// than collected first. Only unsorted, non-recursive, non-grid modes qualify.
+fn can_stream(args: &Args) -> bool {
+    args.no_sort()
+        && !args.is_recursive()
+        && (args.is_long() || args.one_per_line || args.comma || args.zero)
+}
 
+/// Print a directory's entries as they are read (`-U`/`-f`). Long view fixes
+/// its column widths after the first `STREAM_LOOKAHEAD` entries; the `total`
+/// line is only printed when the whole directory fit in that window.
+#[allow(clippy::too_many_arguments)]
+fn stream_entries(
+    out: &mut impl Write,
+    mut entries: entry::DirEntries,
+    args: &Args,
+    use_color: bool,
+    show_icons: bool,
+    term_width: usize,
+    terminator: char,
+    git_repo: Option<&GitRepo>,
+    summary: &mut Summary,
+) -> io::Result<()> {
+    let count = args.summary.is_some();
+    let mut with_git = |mut e: FileEntry| {
+        if let Some(repo) = git_repo {
+            e.git_status = Some(repo.status_for(&e.path));
+        }
+        if count {
+            summary.add(&e);
+        }
+        e
+    };
+
+    if args.is_long() {
+        let window: Vec<FileEntry> = entries
+            .by_ref()
+            .filter(filter::matches)
+            .take(STREAM_LOOKAHEAD)
+            .map(&mut with_git)
+            .collect();
+        if window.len() < STREAM_LOOKAHEAD {
+            writeln!(out, "total {}", format::total_blocks(&window))?;
+        }
+
+        let mut printer = LongPrinter::new(&window, args, use_color, show_icons);
+        if args.header && !window.is_empty() {
+            printer.write_header(out)?;
+        }
+        for entry in &window {
+            printer.write_entry(out, entry)?;
+        }
+        drop(window);
+
+        for entry in entries.filter(filter::matches).map(with_git) {
+            printer.write_entry(out, &entry)?;
+        }
+        return Ok(());
+    }
+
+    // Names are never truncated in -1 / --zero output so it stays pipeline-safe
+    let fit_width = if args.comma && !args.one_per_line {
+        Some(term_width)
+    } else {
+        None
+    };
+    let mut entries = entries.filter(filter::matches).map(with_git).peekable();
+    let mut line_len = 0;
+    let mut any = false;
+
+    while let Some(entry) = entries.next() {
+        let item = format_items(
+            std::slice::from_ref(&entry),
+            args,
+            use_color,
+            show_icons,
+            fit_width,
+        )
+        .remove(0);
+
+        if args.one_per_line {
+            write!(out, "{}{}", item.text, terminator)?;
+        } else if args.comma {
+            let mut chunk = String::new();
+            let is_last = entries.peek().is_none();
+            grid::push_comma_item(&mut chunk, &item, is_last, &mut line_len, term_width);
+            write!(out, "{}", chunk)?;
+            any = true;
+        } else {
+            write!(out, "{}\0", item.text)?;
+        }
+    }
+
+    if any {
+        writeln!(out)?;
+    }
     Ok(())
 }
 
@@ -470,22 +771,28 @@ fn print_recursive(
     out: &mut impl Write,
     dir_path: &str,
     args: &Args,
-    icon_map: &HashMap<&str, IconEntry>,
     use_color: bool,
     show_icons: bool,
     term_width: usize,
     terminator: char,
     base_path: &str,
     git_repo: Option<&GitRepo>,
+    total: &mut Summary,
 ) -> io::Result<()> {
     let path = Path::new(dir_path);
 
-    writeln!(out)?;
-    if use_color {
-        writeln!(out, "\x1b[1;34m{}:\x1b[0m", dir_path)?;
-    } else {
-        writeln!(out, "{}:", dir_path)?;
-    }
+    let write_header = |out: &mut dyn Write| -> io::Result<()> {
+        writeln!(out)?;
+        if use_color {
+            writeln!(
+                out,
+                "{}",
+                theme::paint(Role::DirHeader, &format!("{}:", dir_path))
+            )
+        } else {
+            writeln!(out, "{}:", dir_path)
+        }
+    };
 
     match entry::read_directory(path, args) {
         Ok(mut entries) => {
@@ -495,27 +802,28 @@ fn print_recursive(
             }
 
             entry::sort_entries(&mut entries, args);
+            let shown = display_filter(&entries, args);
 
-            if args.is_long() {
-                let total = format::total_blocks(&entries);
-                writeln!(out, "total {}", total)?;
-            }
+            // --audit and the filters skip directories with nothing to report
+            if !is_filtering(args) || !shown.is_empty() {
+                write_header(out)?;
 
-            print_entries(
-                out, &entries, args, icon_map, use_color, show_icons, term_width, terminator,
-            )?;
+                if args.is_long() {
+                    let total = format::total_blocks(&shown);
+                    writeln!(out, "total {}", total)?;
+                }
+
+                print_entries(
+                    out, &shown, args, use_color, show_icons, term_width, terminator,
+                )?;
+                write_summary(out, &shown, args, use_color, show_icons, total)?;
+            }
 
             // Recurse into subdirectories
-            let mut subdirs: Vec<FileEntry> = entries
+            // Descend in the order the entries were listed
+            let subdirs = entries
                 .iter()
-                .filter(|e| e.is_dir && e.name != "." && e.name != "..")
-                .cloned()
-                .collect();
-            subdirs.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-
-            if args.reverse {
-                subdirs.reverse();
-            }
+                .filter(|e| e.is_dir && e.name != "." && e.name != "..");
 
             for subdir in subdirs {
                 let subpath = subdir.path.to_string_lossy().to_string();
@@ -534,12 +842,13 @@ fn print_recursive(
                 }
 
                 print_recursive(
-                    out, &subpath, args, icon_map, use_color, show_icons, term_width, terminator,
-                    base_path, git_repo,
+                    out, &subpath, args, use_color, show_icons, term_width, terminator, base_path,
+                    git_repo, total,
                 )?;
             }
         }
         Err(e) => {
+            write_header(out)?;
             eprintln!("lsf: cannot open directory '{}': {}", dir_path, e);
         }
     }
@@ -547,22 +856,120 @@ fn print_recursive(
     Ok(())
 }
 
+/// Whether only some entries are shown, with `--audit` or any filter.
+pub fn is_filtering(args: &Args) -> bool {
+    args.audit || filter::is_active()
+}
+
+/// Whether an entry is shown: only risky ones with `--audit`, and only those
+/// passing the type, size, time, owner and permission filters.
+fn is_shown(entry: &FileEntry, args: &Args) -> bool {
+    (!args.audit || entry.is_risky()) && filter::matches(entry)
+}
+
+/// The entries to display: all of them, or those `is_shown` picks.
+fn display_filter<'e>(entries: &'e [FileEntry], args: &Args) -> Cow<'e, [FileEntry]> {
+    if is_filtering(args) {
+        Cow::Owned(
+            entries
+                .iter()
+                .filter(|e| is_shown(e, args))
+                .cloned()
+                .collect(),
+        )
+    } else {
+        Cow::Borrowed(entries)
+    }
+}
+
+/// Sorted directory listings for `--tree` with `--audit` or filters, keyed by
+/// directory path. Each directory keeps only the children that are shown or
+/// lead to one that is, so directories with nothing matching are pruned.
+pub type PrunedTree = HashMap<PathBuf, Vec<FileEntry>>;
+
+/// Read the tree below `root` once, pruning it bottom-up for `--audit` and
+/// the filters.
+pub fn prune_tree(root: &Path, args: &Args, git_repo: Option<&GitRepo>) -> PrunedTree {
+    let mut tree = PrunedTree::new();
+    prune_dir(root, args, git_repo, 0, &mut Vec::new(), &mut tree);
+    tree
+}
+
+/// Add the pruned listing of a directory at `depth` (and those below it, within
+/// `--depth`) to `tree`; returns whether it kept any children. `ancestors`
+/// holds the (device, inode) of the directories above, so a symlink loop
+/// followed with -L is not entered again.
+fn prune_dir(
+    path: &Path,
+    args: &Args,
+    git_repo: Option<&GitRepo>,
+    depth: usize,
+    ancestors: &mut Vec<(u64, u64)>,
+    tree: &mut PrunedTree,
+) -> bool {
+    if args.max_depth.is_some_and(|max| depth >= max) {
+        return false;
+    }
+    let id = match std::fs::metadata(path) {
+        Ok(meta) => (meta.dev(), meta.ino()),
+        Err(_) => return false,
+    };
+    if ancestors.contains(&id) {
+        return false;
+    }
+    let mut children = match entry::read_directory(path, args) {
+        Ok(children) => children,
+        Err(_) => return false,
+    };
+    if let Some(repo) = git_repo {
+        entry::apply_git_status(&mut children, repo);
+    }
+    entry::sort_entries(&mut children, args);
+
+    ancestors.push(id);
+    children.retain(|c| {
+        if c.name == "." || c.name == ".." {
+            return false;
+        }
+        let below = c.is_dir && prune_dir(&c.path, args, git_repo, depth + 1, ancestors, tree);
+        below || is_shown(c, args)
+    });
+    ancestors.pop();
+
+    let kept = !children.is_empty();
+    tree.insert(path.to_path_buf(), children);
+    kept
+}
+
 /// Print entries in tree view.
 #[allow(clippy::too_many_arguments)]
 pub fn print_tree(
     out: &mut impl Write,
     path: &Path,
     args: &Args,
-    icon_map: &HashMap<&str, IconEntry>,
     use_color: bool,
     show_icons: bool,
     prefix: &str,
     is_last: bool,
     depth: usize,
     git_repo: Option<&GitRepo>,
+    pruned: Option<&PrunedTree>,
+    summary: &mut Summary,
 ) -> io::Result<()> {
     let follow = args.dereference;
     let mut entry = FileEntry::from_path(path, follow)?;
+    if use_color || args.audit {
+        entry.load_capabilities();
+    }
+    if args.sniff_content() {
+        entry.load_content_type();
+    }
+    if args.count_in_name() {
+        entry.load_child_count(args);
+    }
+    if args.summary.is_some() && depth > 0 {
+        summary.add(&entry);
+    }
 
     // Apply git status
     if let Some(repo) = git_repo {
@@ -578,10 +985,9 @@ pub fn print_tree(
         "├── "
     };
 
-    let icon_str = if show_icons {
+    let (icon_str, icon_width) = if show_icons {
         let icon = icons::get_icon(
-            icon_map,
-            &entry.name,
+            entry.icon_name(),
             entry.is_dir,
             entry.is_hidden,
             entry.is_symlink,
@@ -591,17 +997,16 @@ pub fn print_tree(
             entry.is_block_device,
             entry.is_char_device,
         );
-        if use_color {
+        let icon_str = if use_color {
             format!("{} ", icon.colored())
         } else {
             format!("{} ", icon.plain())
-        }
+        };
+        (icon_str, icon.width() + 1)
     } else {
-        String::new()
+        (String::new(), 0)
     };
 
-    let colored_name = format::colorize_filename(&entry, use_color);
-
     // Git status marker
     let git_marker = if args.git {
         if let Some(ref status) = entry.git_status {
@@ -618,10 +1023,30 @@ pub fn print_tree(
         String::new()
     };
 
+    let count = if args.count_in_name() {
+        format::count_suffix(&entry, use_color)
+    } else {
+        String::new()
+    };
+
+    let used = grid::display_width(prefix)
+        + grid::display_width(connector)
+        + icon_width
+        + grid::display_width(&count)
+        + grid::display_width(&git_marker);
+    let colored_name = match args.name_width_limit(grid::get_terminal_width(args.term_width), used)
+    {
+        Some(max) => {
+            let name = grid::truncate_middle(&entry.name, max);
+            format::colorize_name(&entry, &name, use_color)
+        }
+        None => format::colorize_filename(&entry, use_color),
+    };
+
     writeln!(
         out,
-        "{}{}{}{}{}",
-        prefix, connector, icon_str, colored_name, git_marker
+        "{}{}{}{}{}{}",
+        prefix, connector, icon_str, colored_name, count, git_marker
     )?;
 
     // Recurse into directories
@@ -632,36 +1057,169 @@ pub fn print_tree(
             }
         }
 
-        if let Ok(mut children) = entry::read_directory(path, args) {
-            entry::sort_entries(&mut children, args);
+        let read;
+        let children = match pruned {
+            Some(tree) => tree.get(path).map_or(&[][..], |c| c.as_slice()),
+            None => match entry::read_directory(path, args) {
+                Ok(mut children) => {
+                    if let Some(repo) = git_repo {
+                        entry::apply_git_status(&mut children, repo);
+                    }
+                    entry::sort_entries(&mut children, args);
+                    read = children;
+                    &read[..]
+                }
+                Err(_) => return Ok(()),
+            },
+        };
 
-            let child_prefix = if depth == 0 {
-                String::new()
-            } else {
-                format!("{}{}", prefix, if is_last { "    " } else { "│   " })
-            };
+        let child_prefix = if depth == 0 {
+            String::new()
+        } else {
+            format!("{}{}", prefix, if is_last { "    " } else { "│   " })
+        };
 
-            let len = children.len();
-            for (i, child) in children.iter().enumerate() {
-                if child.name == "." || child.name == ".." {
-                    continue;
-                }
-                let child_is_last = i == len - 1;
-                print_tree(
-                    out,
-                    &child.path,
-                    args,
-                    icon_map,
-                    use_color,
-                    show_icons,
-                    &child_prefix,
-                    child_is_last,
-                    depth + 1,
-                    git_repo,
-                )?;
+        let len = children.len();
+        for (i, child) in children.iter().enumerate() {
+            if child.name == "." || child.name == ".." {
+                continue;
             }
+            let child_is_last = i == len - 1;
+            print_tree(
+                out,
+                &child.path,
+                args,
+                use_color,
+                show_icons,
+                &child_prefix,
+                child_is_last,
+                depth + 1,
+                git_repo,
+                pruned,
+                summary,
+            )?;
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// A fresh directory under the system temp dir for one test.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lsf-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn names(tree: &PrunedTree, dir: &Path) -> Vec<String> {
+        tree.get(dir).map_or_else(Vec::new, |children| {
+            children.iter().map(|c| c.name.clone()).collect()
+        })
+    }
+
+    #[test]
+    fn audit_prunes_tree() {
+        let root = scratch_dir("audit-tree");
+        for dir in ["plain/deep", "risky/deep"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in ["plain/a", "plain/deep/b", "risky/ok", "risky/deep/s"] {
+            fs::write(root.join(file), "").unwrap();
+        }
+        let setuid = fs::Permissions::from_mode(0o4755);
+        fs::set_permissions(root.join("risky/deep/s"), setuid).unwrap();
+
+        let args = Args::parse_from(["lsf", "--tree", "--audit"]);
+        assert!(is_filtering(&args));
+        let tree = prune_tree(&root, &args, None);
+        assert_eq!(names(&tree, &root), ["risky"]);
+        assert_eq!(names(&tree, &root.join("risky")), ["deep"]);
+        assert_eq!(names(&tree, &root.join("risky/deep")), ["s"]);
+        assert!(names(&tree, &root.join("plain")).is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    /// Stream `dir` with the given flags, returning the output and the
+    /// number of files the summary counted.
+    fn stream(dir: &Path, flags: &[&str]) -> (String, u64) {
+        let args = Args::parse_from(
+            ["lsf", "-U", "--no-icons", "--color=never", "--summary"]
+                .into_iter()
+                .chain(flags.iter().copied()),
+        );
+        assert!(can_stream(&args), "{:?}", flags);
+        let entries = entry::DirEntries::open(dir, &args).unwrap();
+        let mut out = Vec::new();
+        let mut summary = Summary::default();
+        stream_entries(
+            &mut out,
+            entries,
+            &args,
+            false,
+            false,
+            40,
+            '\n',
+            None,
+            &mut summary,
+        )
+        .unwrap();
+        let mut counts = Vec::new();
+        summary
+            .write(&mut counts, "Summary", 0, false, false, false)
+            .unwrap();
+        let counts = String::from_utf8(counts).unwrap();
+        let files = counts["Summary: ".len()..].split(' ').next().unwrap();
+        (String::from_utf8(out).unwrap(), files.parse().unwrap())
+    }
+
+    #[test]
+    fn streaming() {
+        assert!(!can_stream(&Args::parse_from(["lsf", "-l"])));
+        assert!(!can_stream(&Args::parse_from(["lsf", "-U"])));
+        assert!(!can_stream(&Args::parse_from(["lsf", "-U", "-l", "-R"])));
+
+        let small = scratch_dir("stream-small");
+        for (name, size) in [("a", 1), ("b", 22), ("c", 333)] {
+            fs::write(small.join(name), "x".repeat(size)).unwrap();
+        }
+        // The whole directory fits the lookahead: total line and shared widths
+        let (out, files) = stream(&small, &["-l"]);
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines[0].starts_with("total "), "{}", out);
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1..].iter().all(|l| l.len() == lines[1].len()));
+        assert_eq!(files, 3);
+
+        let (one, _) = stream(&small, &["-1"]);
+        let mut names: Vec<&str> = one.lines().collect();
+        names.sort();
+        assert_eq!(names, ["a", "b", "c"]);
+        let (zero, _) = stream(&small, &["--zero"]);
+        assert_eq!(zero.matches('\0').count(), 3);
+        let (comma, _) = stream(&small, &["-m"]);
+        assert_eq!(comma.matches(", ").count(), 2);
+        assert!(comma.ends_with('\n') && !comma.ends_with(", \n"));
+
+        // Past the lookahead: no total line, but every entry is still listed
+        let large = scratch_dir("stream-large");
+        for i in 0..=STREAM_LOOKAHEAD {
+            fs::write(large.join(format!("f{}", i)), "").unwrap();
+        }
+        let (out, files) = stream(&large, &["-l"]);
+        assert!(!out.starts_with("total "));
+        assert_eq!(out.lines().count(), STREAM_LOOKAHEAD + 1);
+        assert_eq!(files, STREAM_LOOKAHEAD as u64 + 1);
+
+        fs::remove_dir_all(&small).unwrap();
+        fs::remove_dir_all(&large).unwrap();
+    }
+}
@@ -0,0 +1,494 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// How many leading bytes are read to detect a file's type. Enough for the
+/// tar header magic at offset 257.
+const SNIFF_LEN: usize = 512;
+
+/// A file type detected from content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentType {
+    pub mime: &'static str,
+    /// Icon table key (an extension like `.py`) for this type, if it has one
+    pub icon_key: Option<&'static str>,
+}
+
+impl ContentType {
+    const fn new(mime: &'static str, icon_key: &'static str) -> Self {
+        ContentType {
+            mime,
+            icon_key: Some(icon_key),
+        }
+    }
+
+    const fn mime_only(mime: &'static str) -> Self {
+        ContentType {
+            mime,
+            icon_key: None,
+        }
+    }
+}
+
+/// Detect the type of a regular file from its first bytes: shebang lines,
+/// executable headers and common magic numbers. `name` is used to tell apart
+/// formats that share a container (a `.docx` is a zip) and to refine plain
+/// text. Returns None if the file can't be read.
+pub fn sniff(path: &Path, name: &str) -> Option<ContentType> {
+    let mut buf = [0u8; SNIFF_LEN];
+    let mut file = File::open(path).ok()?;
+    let mut len = 0;
+    while len < buf.len() {
+        match file.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(_) => return None,
+        }
+    }
+    Some(detect(&buf[..len], name))
+}
+
+fn detect(data: &[u8], name: &str) -> ContentType {
+    if data.is_empty() {
+        return ContentType::mime_only("inode/x-empty");
+    }
+    if let Some(ct) = executable(data, name) {
+        return ct;
+    }
+    if data.starts_with(b"#!") {
+        return script(data);
+    }
+    if let Some(ct) = magic(data, name) {
+        return ct;
+    }
+    if is_text(data) {
+        return text_by_extension(name);
+    }
+    ContentType::mime_only("application/octet-stream")
+}
+
+/// ELF, Mach-O, PE and Java class files.
+fn executable(data: &[u8], name: &str) -> Option<ContentType> {
+    if data.starts_with(b"\x7fELF") {
+        // e_type: 1 relocatable, 2 executable, 3 shared object (or PIE),
+        // 4 core dump
+        let little_endian = data.get(5) == Some(&1);
+        let e_type = match (data.get(16), data.get(17)) {
+            (Some(&a), Some(&b)) if little_endian => u16::from_le_bytes([a, b]),
+            (Some(&a), Some(&b)) => u16::from_be_bytes([a, b]),
+            _ => 0,
+        };
+        // PIE executables are shared objects too; only the name tells a
+        // library ("libfoo.so", "libfoo.so.1.2") apart
+        let is_library = name.ends_with(".so") || name.contains(".so.");
+        return Some(match e_type {
+            1 => ContentType::new("application/x-object", ".o"),
+            3 if is_library => ContentType::new("application/x-sharedlib", ".so"),
+            3 => ContentType::new("application/x-pie-executable", ".elf"),
+            4 => ContentType::mime_only("application/x-coredump"),
+            _ => ContentType::new("application/x-executable", ".elf"),
+        });
+    }
+
+    const MACHO: [&[u8]; 4] = [
+        b"\xfe\xed\xfa\xce",
+        b"\xfe\xed\xfa\xcf",
+        b"\xce\xfa\xed\xfe",
+        b"\xcf\xfa\xed\xfe",
+    ];
+    if MACHO.iter().any(|m| data.starts_with(m)) {
+        return Some(ContentType::new("application/x-mach-binary", ".app"));
+    }
+
+    if data.starts_with(b"\xca\xfe\xba\xbe") && data.len() >= 8 {
+        // Shared by universal Mach-O binaries (an architecture count) and
+        // Java classes (a class file version, always 45 or more)
+        let n = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+        return Some(if n < 45 {
+            ContentType::new("application/x-mach-binary", ".app")
+        } else {
+            ContentType::new("application/java-vm", ".class")
+        });
+    }
+
+    if data.starts_with(b"MZ") {
+        let is_dll = name.to_ascii_lowercase().ends_with(".dll");
+        return Some(ContentType::new(
+            "application/vnd.microsoft.portable-executable",
+            if is_dll { ".dll" } else { ".exe" },
+        ));
+    }
+
+    if data.starts_with(b"\0asm") {
+        return Some(ContentType::new("application/wasm", ".wasm"));
+    }
+
+    None
+}
+
+/// Classify a script by the interpreter on its `#!` line.
+fn script(data: &[u8]) -> ContentType {
+    let line = data[2..].split(|&b| b == b'\n').next().unwrap_or_default();
+    let line = String::from_utf8_lossy(line);
+    let mut words = line.split_whitespace();
+    let mut interpreter = words.next().unwrap_or("").rsplit('/').next().unwrap_or("");
+
+    // "#!/usr/bin/env [-S] python3"
+    if interpreter == "env" {
+        interpreter = words.find(|w| !w.starts_with('-')).unwrap_or("");
+    }
+
+    // "python3.12" -> "python"
+    let base = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    match base {
+        "python" | "pypy" => ContentType::new("text/x-python", ".py"),
+        "sh" | "bash" | "dash" | "ksh" | "mksh" | "ash" | "busybox" => {
+            ContentType::new("application/x-shellscript", ".sh")
+        }
+        "zsh" => ContentType::new("application/x-shellscript", ".zsh"),
+        "fish" => ContentType::new("application/x-shellscript", ".fish"),
+        "csh" | "tcsh" => ContentType::new("application/x-csh", ".csh"),
+        "node" | "nodejs" | "deno" | "bun" => ContentType::new("text/javascript", ".js"),
+        "ruby" => ContentType::new("text/x-ruby", ".rb"),
+        "perl" => ContentType::new("text/x-perl", ".pl"),
+        "php" => ContentType::new("application/x-php", ".php"),
+        "lua" | "luajit" => ContentType::new("text/x-lua", ".lua"),
+        "Rscript" => ContentType::new("text/x-r", ".r"),
+        "pwsh" => ContentType::new("text/x-powershell", ".ps1"),
+        _ => ContentType::new("text/x-script", ".sh"),
+    }
+}
+
+/// Zip-based formats recognized by extension once the zip magic matches.
+const ZIP_FORMATS: [(&str, &str); 10] = [
+    (".jar", "application/java-archive"),
+    (".war", "application/java-archive"),
+    (".apk", "application/vnd.android.package-archive"),
+    (
+        ".docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    (
+        ".xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    (
+        ".pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    ),
+    (".odt", "application/vnd.oasis.opendocument.text"),
+    (".ods", "application/vnd.oasis.opendocument.spreadsheet"),
+    (".odp", "application/vnd.oasis.opendocument.presentation"),
+    (".epub", "application/epub+zip"),
+];
+
+/// Images, audio, video, archives, documents and databases by magic number.
+fn magic(data: &[u8], name: &str) -> Option<ContentType> {
+    const MAGIC: &[(&[u8], ContentType)] = &[
+        (b"\x89PNG\r\n\x1a\n", ContentType::new("image/png", ".png")),
+        (b"\xff\xd8\xff", ContentType::new("image/jpeg", ".jpg")),
+        (b"GIF87a", ContentType::new("image/gif", ".gif")),
+        (b"GIF89a", ContentType::new("image/gif", ".gif")),
+        (b"II*\0", ContentType::new("image/tiff", ".tiff")),
+        (b"MM\0*", ContentType::new("image/tiff", ".tiff")),
+        (
+            b"\0\0\x01\0",
+            ContentType::new("image/vnd.microsoft.icon", ".ico"),
+        ),
+        (
+            b"8BPS",
+            ContentType::new("image/vnd.adobe.photoshop", ".psd"),
+        ),
+        (b"%PDF-", ContentType::new("application/pdf", ".pdf")),
+        (b"%!PS", ContentType::new("application/postscript", ".eps")),
+        (b"\x1f\x8b", ContentType::new("application/gzip", ".gz")),
+        (b"BZh", ContentType::new("application/x-bzip2", ".bz2")),
+        (b"\xfd7zXZ\0", ContentType::new("application/x-xz", ".xz")),
+        (
+            b"\x28\xb5\x2f\xfd",
+            ContentType::new("application/zstd", ".zst"),
+        ),
+        (
+            b"7z\xbc\xaf\x27\x1c",
+            ContentType::new("application/x-7z-compressed", ".7z"),
+        ),
+        (
+            b"Rar!\x1a\x07",
+            ContentType::new("application/vnd.rar", ".rar"),
+        ),
+        (
+            b"\x04\x22\x4d\x18",
+            ContentType::new("application/x-lz4", ".lz4"),
+        ),
+        (
+            b"!<arch>\ndebian",
+            ContentType::new("application/vnd.debian.binary-package", ".deb"),
+        ),
+        (
+            b"!<arch>\n",
+            ContentType::new("application/x-archive", ".a"),
+        ),
+        (
+            b"\xed\xab\xee\xdb",
+            ContentType::new("application/x-rpm", ".rpm"),
+        ),
+        (
+            b"SQLite format 3\0",
+            ContentType::new("application/vnd.sqlite3", ".sqlite"),
+        ),
+        (b"OggS", ContentType::new("audio/ogg", ".ogg")),
+        (b"fLaC", ContentType::new("audio/flac", ".flac")),
+        (b"ID3", ContentType::new("audio/mpeg", ".mp3")),
+        (b"MThd", ContentType::new("audio/midi", ".mid")),
+        (
+            b"\x1a\x45\xdf\xa3",
+            ContentType::new("video/x-matroska", ".mkv"),
+        ),
+        (b"wOFF", ContentType::new("font/woff", ".woff")),
+        (b"wOF2", ContentType::new("font/woff2", ".woff2")),
+        (b"\0\x01\0\0\0", ContentType::new("font/ttf", ".ttf")),
+        (b"OTTO", ContentType::new("font/otf", ".otf")),
+        (
+            b"-----BEGIN PGP",
+            ContentType::new("application/pgp-keys", ".asc"),
+        ),
+        (
+            b"-----BEGIN ",
+            ContentType::new("application/x-pem-file", ".pem"),
+        ),
+    ];
+
+    if let Some((_, ct)) = MAGIC.iter().find(|(m, _)| data.starts_with(m)) {
+        return Some(*ct);
+    }
+
+    if data.starts_with(b"PK\x03\x04") || data.starts_with(b"PK\x05\x06") {
+        let lower = name.to_ascii_lowercase();
+        return Some(
+            ZIP_FORMATS
+                .iter()
+                .find(|(ext, _)| lower.ends_with(ext))
+                .map(|&(ext, mime)| ContentType::new(mime, ext))
+                .unwrap_or(ContentType::new("application/zip", ".zip")),
+        );
+    }
+
+    // RIFF containers: WebP, WAV, AVI
+    if data.starts_with(b"RIFF") && data.len() >= 12 {
+        return match &data[8..12] {
+            b"WEBP" => Some(ContentType::new("image/webp", ".webp")),
+            b"WAVE" => Some(ContentType::new("audio/wav", ".wav")),
+            b"AVI " => Some(ContentType::new("video/x-msvideo", ".avi")),
+            _ => None,
+        };
+    }
+
+    // ISO base media files: MP4, QuickTime, HEIC, AVIF, M4A
+    if data.len() >= 12 && &data[4..8] == b"ftyp" {
+        return Some(match &data[8..12] {
+            b"heic" | b"heix" | b"mif1" => ContentType::new("image/heic", ".heic"),
+            b"avif" => ContentType::new("image/avif", ".avif"),
+            b"qt  " => ContentType::new("video/quicktime", ".mov"),
+            b"M4A " => ContentType::new("audio/mp4", ".m4a"),
+            _ => ContentType::new("video/mp4", ".mp4"),
+        });
+    }
+
+    if data.len() >= 262 && &data[257..262] == b"ustar" {
+        return Some(ContentType::new("application/x-tar", ".tar"));
+    }
+
+    // Markup, by its opening tag
+    let head = String::from_utf8_lossy(&data[..data.len().min(256)]).to_ascii_lowercase();
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    if head.starts_with("<svg") || (head.starts_with("<?xml") && head.contains("<svg")) {
+        return Some(ContentType::new("image/svg+xml", ".svg"));
+    }
+    if head.starts_with("<?xml") {
+        return Some(ContentType::new("text/xml", ".xml"));
+    }
+    if head.starts_with("<!doctype html") || head.starts_with("<html") {
+        return Some(ContentType::new("text/html", ".html"));
+    }
+
+    None
+}
+
+/// Whether the sample looks like text: no NUL bytes and valid UTF-8, allowing
+/// a character cut off at the end of the sample.
+fn is_text(data: &[u8]) -> bool {
+    if data.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(data) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+/// MIME type of a text file from its extension, or `text/plain`. Text files
+/// keep their name-based icons.
+fn text_by_extension(name: &str) -> ContentType {
+    let ext = match name.rfind('.') {
+        Some(pos) if pos > 0 => name[pos + 1..].to_ascii_lowercase(),
+        _ => String::new(),
+    };
+    let mime = match ext.as_str() {
+        "md" | "markdown" => "text/markdown",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "js" | "mjs" | "cjs" => "text/javascript",
+        "ts" => "text/x-typescript",
+        "json" => "application/json",
+        "yaml" | "yml" => "application/yaml",
+        "toml" => "application/toml",
+        "xml" => "text/xml",
+        "py" => "text/x-python",
+        "rs" => "text/rust",
+        "c" | "h" => "text/x-c",
+        "cpp" | "cc" | "hpp" => "text/x-c++",
+        "go" => "text/x-go",
+        "java" => "text/x-java",
+        "rb" => "text/x-ruby",
+        "sh" | "bash" | "zsh" => "application/x-shellscript",
+        "sql" => "application/sql",
+        _ => "text/plain",
+    };
+    ContentType::mime_only(mime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An ELF header prefix with the given data encoding (1 little, 2 big
+    /// endian) and e_type.
+    fn elf(encoding: u8, e_type: u16) -> Vec<u8> {
+        let mut data = b"\x7fELF\x02".to_vec();
+        data.push(encoding);
+        data.resize(16, 0);
+        let bytes = if encoding == 1 {
+            e_type.to_le_bytes()
+        } else {
+            e_type.to_be_bytes()
+        };
+        data.extend_from_slice(&bytes);
+        data
+    }
+
+    fn mime(data: &[u8], name: &str) -> &'static str {
+        detect(data, name).mime
+    }
+
+    #[test]
+    fn elf_types() {
+        let cases = [
+            (elf(1, 1), "main.o", "application/x-object"),
+            (elf(1, 2), "prog", "application/x-executable"),
+            (elf(2, 2), "prog", "application/x-executable"),
+            (elf(1, 3), "prog", "application/x-pie-executable"),
+            (elf(2, 3), "prog", "application/x-pie-executable"),
+            (elf(1, 3), "libfoo.so", "application/x-sharedlib"),
+            (elf(1, 3), "libfoo.so.1.2", "application/x-sharedlib"),
+            (elf(1, 3), "my.social", "application/x-pie-executable"),
+            (elf(1, 4), "core", "application/x-coredump"),
+            (b"\x7fELF".to_vec(), "short", "application/x-executable"),
+        ];
+        for (data, name, expected) in cases {
+            assert_eq!(mime(&data, name), expected, "{}", name);
+        }
+        assert_eq!(detect(&elf(1, 3), "libfoo.so").icon_key, Some(".so"));
+    }
+
+    #[test]
+    fn cafebabe() {
+        let fat = b"\xca\xfe\xba\xbe\0\0\0\x02";
+        let class = b"\xca\xfe\xba\xbe\0\0\0\x34";
+        assert_eq!(mime(fat, "tool"), "application/x-mach-binary");
+        assert_eq!(mime(class, "Main.class"), "application/java-vm");
+        assert_eq!(mime(b"\xca\xfe\xba\xbe", "x"), "application/octet-stream");
+        assert_eq!(
+            mime(b"\xcf\xfa\xed\xfe\x07", "tool"),
+            "application/x-mach-binary"
+        );
+    }
+
+    #[test]
+    fn shebangs() {
+        let cases = [
+            ("#!/bin/sh\n", "application/x-shellscript", ".sh"),
+            (
+                "#!/usr/bin/env bash\necho",
+                "application/x-shellscript",
+                ".sh",
+            ),
+            ("#!/usr/bin/env -S python3 -u\n", "text/x-python", ".py"),
+            ("#!/usr/bin/python3.12\n", "text/x-python", ".py"),
+            ("#! /usr/bin/perl -w\n", "text/x-perl", ".pl"),
+            ("#!/usr/bin/env node", "text/javascript", ".js"),
+            ("#!/usr/bin/zsh -f\n", "application/x-shellscript", ".zsh"),
+            ("#!/opt/custom/interp\n", "text/x-script", ".sh"),
+            ("#!", "text/x-script", ".sh"),
+            ("#!/usr/bin/env\n", "text/x-script", ".sh"),
+        ];
+        for (data, mime, icon) in cases {
+            let ct = detect(data.as_bytes(), "script");
+            assert_eq!((ct.mime, ct.icon_key), (mime, Some(icon)), "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn magic_numbers() {
+        let mut tar = vec![b'a'; 512];
+        tar[100] = 0;
+        tar[257..263].copy_from_slice(b"ustar\0");
+        let mut short_tar = tar.clone();
+        short_tar.truncate(261);
+
+        let mut docx = b"PK\x03\x04".to_vec();
+        docx.resize(30, 0);
+
+        let cases: [(&[u8], &str, &str); 12] = [
+            (&tar, "backup", "application/x-tar"),
+            (&short_tar, "backup", "application/octet-stream"),
+            (b"\x89PNG\r\n\x1a\n", "img", "image/png"),
+            (b"%PDF-1.7", "doc", "application/pdf"),
+            (
+                &docx,
+                "report.DOCX",
+                "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+            ),
+            (&docx, "bundle", "application/zip"),
+            (b"RIFF\0\0\0\0WEBPVP8 ", "pic", "image/webp"),
+            (b"RIFF\0\0", "pic", "application/octet-stream"),
+            (b"\0\0\0\x18ftypheic", "photo", "image/heic"),
+            (
+                b"!<arch>\ndebian-binary",
+                "pkg",
+                "application/vnd.debian.binary-package",
+            ),
+            (b"\xef\xbb\xbf<svg xmlns=", "logo", "image/svg+xml"),
+            (b"  <!DOCTYPE html>", "page", "text/html"),
+        ];
+        for (data, name, expected) in cases {
+            assert_eq!(mime(data, name), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn empty_and_text() {
+        let cases: [(&[u8], &str, &str); 7] = [
+            (b"", "empty", "inode/x-empty"),
+            (b"#", "x", "text/plain"),
+            (b"M", "x", "text/plain"),
+            (b"hello\n", "notes.md", "text/markdown"),
+            (b"hello\n", ".bashrc", "text/plain"),
+            (b"caf\xc3", "cut.txt", "text/plain"),
+            (b"a\0b", "bin", "application/octet-stream"),
+        ];
+        for (data, name, expected) in cases {
+            assert_eq!(mime(data, name), expected, "{:?}", data);
+        }
+    }
+}
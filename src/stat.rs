@@ -0,0 +1,146 @@
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::fileattr::FileAttributes;
+
+/// The metadata lsf displays, from a single statx call where available (so
+/// inode attributes come with the rest), else from std's stat.
+#[derive(Debug, Clone, Copy)]
+pub struct Stat {
+    pub mode: u32,
+    pub nlink: u64,
+    pub uid: u32,
+    pub gid: u32,
+    pub size: u64,
+    pub blocks: u64,
+    pub ino: u64,
+    pub rdev: u64,
+    pub accessed: Option<SystemTime>,
+    pub modified: Option<SystemTime>,
+    pub changed: Option<SystemTime>,
+    pub created: Option<SystemTime>,
+    /// Inode attribute flags, if the filesystem reports them through statx
+    pub attributes: Option<FileAttributes>,
+}
+
+/// Stat a path, following a final symlink only when `follow` is set.
+pub fn stat(path: &Path, follow: bool) -> io::Result<Stat> {
+    match sys::statx(path, follow) {
+        Some(result) => result,
+        None => std_stat(path, follow),
+    }
+}
+
+fn std_stat(path: &Path, follow: bool) -> io::Result<Stat> {
+    use std::os::unix::fs::MetadataExt;
+
+    let meta = if follow {
+        std::fs::metadata(path)?
+    } else {
+        std::fs::symlink_metadata(path)?
+    };
+    Ok(Stat {
+        mode: meta.mode(),
+        nlink: meta.nlink(),
+        uid: meta.uid(),
+        gid: meta.gid(),
+        size: meta.len(),
+        blocks: meta.blocks(),
+        ino: meta.ino(),
+        rdev: meta.rdev(),
+        accessed: meta.accessed().ok(),
+        modified: meta.modified().ok(),
+        changed: unix_time(meta.ctime(), meta.ctime_nsec()),
+        created: meta.created().ok(),
+        attributes: None,
+    })
+}
+
+/// A timestamp from seconds and nanoseconds since the epoch.
+fn unix_time(secs: i64, nsecs: i64) -> Option<SystemTime> {
+    use std::time::Duration;
+
+    let nsecs = u32::try_from(nsecs).ok()?;
+    if secs >= 0 {
+        SystemTime::UNIX_EPOCH.checked_add(Duration::new(secs as u64, nsecs))
+    } else {
+        SystemTime::UNIX_EPOCH
+            .checked_sub(Duration::from_secs(secs.unsigned_abs()))?
+            .checked_add(Duration::from_nanos(u64::from(nsecs)))
+    }
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+mod sys {
+    use std::ffi::CString;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    use super::{unix_time, Stat};
+    use crate::fileattr::FileAttributes;
+
+    /// Stat with statx. Returns None when the kernel lacks statx (or a
+    /// seccomp filter blocks it), so the caller can fall back to stat.
+    pub fn statx(path: &Path, follow: bool) -> Option<io::Result<Stat>> {
+        let cpath = match CString::new(path.as_os_str().as_bytes()) {
+            Ok(c) => c,
+            Err(e) => return Some(Err(e.into())),
+        };
+        let mut flags = libc::AT_STATX_SYNC_AS_STAT;
+        if !follow {
+            flags |= libc::AT_SYMLINK_NOFOLLOW;
+        }
+        let mut stx: libc::statx = unsafe { std::mem::zeroed() };
+        let ret = unsafe {
+            libc::statx(
+                libc::AT_FDCWD,
+                cpath.as_ptr(),
+                flags,
+                libc::STATX_BASIC_STATS | libc::STATX_BTIME,
+                &mut stx,
+            )
+        };
+        if ret != 0 {
+            let err = io::Error::last_os_error();
+            return match err.raw_os_error() {
+                Some(libc::ENOSYS) | Some(libc::EPERM) => None,
+                _ => Some(Err(err)),
+            };
+        }
+
+        let time = |t: libc::statx_timestamp| unix_time(t.tv_sec, i64::from(t.tv_nsec));
+        Some(Ok(Stat {
+            mode: u32::from(stx.stx_mode),
+            nlink: u64::from(stx.stx_nlink),
+            uid: stx.stx_uid,
+            gid: stx.stx_gid,
+            size: stx.stx_size,
+            blocks: stx.stx_blocks,
+            ino: stx.stx_ino,
+            rdev: libc::makedev(stx.stx_rdev_major, stx.stx_rdev_minor),
+            accessed: time(stx.stx_atime),
+            modified: time(stx.stx_mtime),
+            changed: time(stx.stx_ctime),
+            created: if stx.stx_mask & libc::STATX_BTIME != 0 {
+                time(stx.stx_btime)
+            } else {
+                None
+            },
+            attributes: FileAttributes::from_statx(stx.stx_attributes, stx.stx_attributes_mask),
+        }))
+    }
+}
+
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+mod sys {
+    use std::io;
+    use std::path::Path;
+
+    use super::Stat;
+
+    pub fn statx(_path: &Path, _follow: bool) -> Option<io::Result<Stat>> {
+        None
+    }
+}
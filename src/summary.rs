@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::entry::FileEntry;
+use crate::format;
+use crate::grid;
+use crate::icons;
+use crate::theme::{self, Role};
+
+/// Totals for `--summary`: entries by type, apparent size and counts by
+/// extension.
+#[derive(Debug, Default)]
+pub struct Summary {
+    files: u64,
+    dirs: u64,
+    links: u64,
+    special: u64,
+    /// Apparent size of everything but directories, in bytes
+    size: u64,
+    /// Lowercase extension -> (number of files, total size)
+    extensions: HashMap<String, (u64, u64)>,
+}
+
+impl Summary {
+    /// Count an entry. `.` and `..` are skipped.
+    pub fn add(&mut self, entry: &FileEntry) {
+        if entry.name == "." || entry.name == ".." {
+            return;
+        }
+        if entry.is_symlink {
+            self.links += 1;
+        } else if entry.is_dir {
+            self.dirs += 1;
+            return;
+        } else if entry.mode == 0 {
+            // Its metadata could not be read, so its type is unknown
+            return;
+        } else if entry.mode & 0o170000 == 0o100000 {
+            self.files += 1;
+            if is_type_extension(&entry.extension) {
+                let ext = self
+                    .extensions
+                    .entry(entry.extension.to_lowercase())
+                    .or_default();
+                ext.0 += 1;
+                ext.1 += entry.size;
+            }
+        } else {
+            self.special += 1;
+        }
+        self.size += entry.size;
+    }
+
+    /// Add another summary's counts to this one.
+    pub fn merge(&mut self, other: &Summary) {
+        self.files += other.files;
+        self.dirs += other.dirs;
+        self.links += other.links;
+        self.special += other.special;
+        self.size += other.size;
+        for (ext, &(count, size)) in &other.extensions {
+            let total = self.extensions.entry(ext.clone()).or_default();
+            total.0 += count;
+            total.1 += size;
+        }
+    }
+
+    /// Write the summary line, headed by `label` ("Summary" or "Total"), then
+    /// the `top` most common extensions with their icons, counts and sizes.
+    pub fn write(
+        &self,
+        out: &mut impl Write,
+        label: &str,
+        top: usize,
+        si: bool,
+        use_color: bool,
+        show_icons: bool,
+    ) -> io::Result<()> {
+        let plural =
+            |n: u64, one: &str, many: &str| format!("{} {}", n, if n == 1 { one } else { many });
+        let label = format!("{}:", label);
+        writeln!(
+            out,
+            "{} {}, {}, {}, {}; total size {}",
+            if use_color {
+                theme::paint(Role::ColumnHeader, &label)
+            } else {
+                label
+            },
+            plural(self.files, "file", "files"),
+            plural(self.dirs, "directory", "directories"),
+            plural(self.links, "link", "links"),
+            plural(self.special, "special", "special"),
+            size_with_unit(self.size, si)
+        )?;
+
+        let extensions = self.top_extensions(top);
+        let ext_width = extensions
+            .iter()
+            .map(|(e, _)| grid::display_width(e))
+            .max()
+            .unwrap_or(0);
+        let count_width = extensions
+            .iter()
+            .map(|(_, (n, _))| n.to_string().len())
+            .max()
+            .unwrap_or(0);
+        for (ext, &(count, size)) in extensions {
+            let icon = if show_icons {
+                let icon = icons::get_icon(
+                    &format!("file.{}", ext),
+                    false,
+                    false,
+                    false,
+                    false,
+                    false,
+                    false,
+                    false,
+                    false,
+                );
+                if use_color {
+                    format!("{} ", icon.colored())
+                } else {
+                    format!("{} ", icon.plain())
+                }
+            } else {
+                String::new()
+            };
+            writeln!(
+                out,
+                "  {}.{}{} {:>cw$} {:>6}",
+                icon,
+                ext,
+                " ".repeat(ext_width - grid::display_width(ext)),
+                count,
+                size_with_unit(size, si),
+                cw = count_width
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The `top` extensions with the most files, then the largest total
+    /// size, then by name.
+    fn top_extensions(&self, top: usize) -> Vec<(&String, &(u64, u64))> {
+        let mut extensions: Vec<(&String, &(u64, u64))> = self.extensions.iter().collect();
+        extensions.sort_by(|a, b| {
+            (b.1 .0, b.1 .1)
+                .cmp(&(a.1 .0, a.1 .1))
+                .then_with(|| a.0.cmp(b.0))
+        });
+        extensions.truncate(top);
+        extensions
+    }
+}
+
+/// Whether an extension names a file type worth tallying: not empty, not a
+/// version number (`libfoo.so.9`) and not a backup suffix (`notes.txt~`).
+fn is_type_extension(ext: &str) -> bool {
+    !ext.is_empty() && !ext.ends_with('~') && !ext.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// A human-readable size that always carries a unit: `25 B`, `4.8M`.
+fn size_with_unit(size: u64, si: bool) -> String {
+    let base = if si { 1000 } else { 1024 };
+    if size < base {
+        format!("{} B", size)
+    } else {
+        format::format_size(size, true, si)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An entry with the given name, file type bits and size.
+    fn entry(name: &str, mode: u32, size: u64) -> FileEntry {
+        let mut entry = FileEntry::from_path(&std::env::temp_dir(), false).unwrap();
+        entry.name = name.to_string();
+        entry.path = name.into();
+        entry.extension = entry
+            .path
+            .extension()
+            .map(|e| e.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        entry.is_dir = mode & 0o170000 == 0o040000;
+        entry.is_symlink = mode & 0o170000 == 0o120000;
+        entry.mode = mode;
+        entry.size = size;
+        entry
+    }
+
+    fn totals(summary: &Summary) -> (u64, u64, u64, u64, u64) {
+        (
+            summary.files,
+            summary.dirs,
+            summary.links,
+            summary.special,
+            summary.size,
+        )
+    }
+
+    #[test]
+    fn adding_entries() {
+        let cases = [
+            ("a.txt", 0o100644, 10, (1, 0, 0, 0, 10), Some("txt")),
+            ("B.TXT", 0o100644, 10, (1, 0, 0, 0, 10), Some("txt")),
+            ("src", 0o040755, 4096, (0, 1, 0, 0, 0), None),
+            ("link.txt", 0o120777, 5, (0, 0, 1, 0, 5), None),
+            ("fifo", 0o010644, 0, (0, 0, 0, 1, 0), None),
+            ("sda", 0o060660, 0, (0, 0, 0, 1, 0), None),
+            ("unreadable.txt", 0, 0, (0, 0, 0, 0, 0), None),
+            (".", 0o040755, 4096, (0, 0, 0, 0, 0), None),
+            ("..", 0o040755, 4096, (0, 0, 0, 0, 0), None),
+            ("libc.so.6", 0o100755, 7, (1, 0, 0, 0, 7), None),
+            ("notes~", 0o100644, 3, (1, 0, 0, 0, 3), None),
+        ];
+        for (name, mode, size, expected, ext) in cases {
+            let mut summary = Summary::default();
+            summary.add(&entry(name, mode, size));
+            assert_eq!(totals(&summary), expected, "{}", name);
+            let exts: Vec<&String> = summary.extensions.keys().collect();
+            assert_eq!(exts, ext.iter().collect::<Vec<_>>(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn merging() {
+        let mut a = Summary::default();
+        a.add(&entry("a.rs", 0o100644, 100));
+        a.add(&entry("dir", 0o040755, 4096));
+        let mut b = Summary::default();
+        b.add(&entry("b.rs", 0o100644, 50));
+        b.add(&entry("c.md", 0o100644, 5));
+        b.add(&entry("l", 0o120777, 1));
+
+        a.merge(&b);
+        assert_eq!(totals(&a), (3, 1, 1, 0, 156));
+        assert_eq!(a.extensions["rs"], (2, 150));
+        assert_eq!(a.extensions["md"], (1, 5));
+        // Merging an empty summary changes nothing
+        a.merge(&Summary::default());
+        assert_eq!(totals(&a), (3, 1, 1, 0, 156));
+    }
+
+    #[test]
+    fn type_extensions() {
+        let cases = [
+            ("txt", true),
+            ("tar", true),
+            ("mp3", true),
+            ("7z", true),
+            ("", false),
+            ("9", false),
+            ("123", false),
+            ("txt~", false),
+            ("~", false),
+        ];
+        for (ext, expected) in cases {
+            assert_eq!(is_type_extension(ext), expected, "{:?}", ext);
+        }
+    }
+
+    #[test]
+    fn extension_ranking() {
+        let mut summary = Summary::default();
+        for (name, size) in [
+            ("a.rs", 1),
+            ("b.rs", 1),
+            ("a.md", 10),
+            ("b.md", 10),
+            ("a.toml", 1),
+            ("a.c", 1),
+            ("a.zip", 100),
+        ] {
+            summary.add(&entry(name, 0o100644, size));
+        }
+        let ranked: Vec<&str> = summary
+            .top_extensions(10)
+            .iter()
+            .map(|(ext, _)| ext.as_str())
+            .collect();
+        // More files first, then larger, then by name
+        assert_eq!(ranked, ["md", "rs", "zip", "c", "toml"]);
+        assert_eq!(summary.top_extensions(2).len(), 2);
+        assert!(summary.top_extensions(0).is_empty());
+    }
+}
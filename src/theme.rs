@@ -0,0 +1,635 @@
+use std::sync::OnceLock;
+
+use crate::cli::Args;
+
+/// What a piece of colored output represents. Each role has a default SGR
+/// style; themes recolor them through their palette and overrides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Dir,
+    DirSticky,
+    DirOtherWritable,
+    DirStickyOtherWritable,
+    DirHeader,
+    ColumnHeader,
+    DirCount,
+    Symlink,
+    SymlinkTarget,
+    Executable,
+    Setuid,
+    Setgid,
+    Capability,
+    Pipe,
+    Socket,
+    Device,
+    Archive,
+    Image,
+    Audio,
+    Video,
+    Immutable,
+    PermDir,
+    PermLink,
+    PermRead,
+    PermWrite,
+    PermExec,
+    PermSpecial,
+    PermNone,
+    AttrImmutable,
+    AttrAppend,
+    AttrOther,
+    OwnerYou,
+    OwnerOther,
+    GroupYours,
+    GitModified,
+    GitStaged,
+    GitUntracked,
+    GitRenamed,
+    GitDeleted,
+    GitConflicted,
+    GitIgnored,
+}
+
+impl Role {
+    /// Every role, in declaration order (so `role as usize` indexes it).
+    const ALL: [Role; 41] = [
+        Role::Dir,
+        Role::DirSticky,
+        Role::DirOtherWritable,
+        Role::DirStickyOtherWritable,
+        Role::DirHeader,
+        Role::ColumnHeader,
+        Role::DirCount,
+        Role::Symlink,
+        Role::SymlinkTarget,
+        Role::Executable,
+        Role::Setuid,
+        Role::Setgid,
+        Role::Capability,
+        Role::Pipe,
+        Role::Socket,
+        Role::Device,
+        Role::Archive,
+        Role::Image,
+        Role::Audio,
+        Role::Video,
+        Role::Immutable,
+        Role::PermDir,
+        Role::PermLink,
+        Role::PermRead,
+        Role::PermWrite,
+        Role::PermExec,
+        Role::PermSpecial,
+        Role::PermNone,
+        Role::AttrImmutable,
+        Role::AttrAppend,
+        Role::AttrOther,
+        Role::OwnerYou,
+        Role::OwnerOther,
+        Role::GroupYours,
+        Role::GitModified,
+        Role::GitStaged,
+        Role::GitUntracked,
+        Role::GitRenamed,
+        Role::GitDeleted,
+        Role::GitConflicted,
+        Role::GitIgnored,
+    ];
+
+    /// Whether `ALL[i]` is the role with discriminant `i` for every `i`, and
+    /// the array covers every variant up to the last one declared.
+    const fn all_is_indexed() -> bool {
+        let mut i = 0;
+        while i < Role::ALL.len() {
+            if Role::ALL[i] as usize != i {
+                return false;
+            }
+            i += 1;
+        }
+        Role::ALL.len() == Role::GitIgnored as usize + 1
+    }
+
+    /// The style used by the default theme, as an SGR parameter string.
+    fn default_sgr(self) -> &'static str {
+        match self {
+            Role::Dir | Role::DirHeader | Role::PermDir => "1;34",
+            Role::DirSticky => "37;44",
+            Role::DirOtherWritable => "34;42",
+            Role::DirStickyOtherWritable => "30;42",
+            Role::Symlink | Role::SymlinkTarget => "36",
+            Role::Executable | Role::PermExec => "1;32",
+            Role::Setuid => "37;41",
+            Role::Setgid => "30;43",
+            Role::Capability => "30;41",
+            Role::Pipe => "33",
+            Role::Socket | Role::Video => "1;35",
+            Role::Device => "1;33",
+            Role::Archive => "1;31",
+            Role::Image => "35",
+            Role::Audio => "36",
+            Role::Immutable | Role::ColumnHeader => "4",
+            Role::PermLink => "1;36",
+            Role::PermRead => "33",
+            Role::PermWrite => "31",
+            Role::PermSpecial => "32",
+            Role::PermNone | Role::DirCount => "90",
+            Role::AttrImmutable => "1;31",
+            Role::AttrAppend => "33",
+            Role::AttrOther => "36",
+            Role::OwnerYou => "1;33",
+            Role::OwnerOther => "31",
+            Role::GroupYours => "33",
+            Role::GitModified => "33",
+            Role::GitStaged => "32",
+            Role::GitUntracked => "31",
+            Role::GitRenamed => "36",
+            Role::GitDeleted => "31",
+            Role::GitConflicted => "1;31",
+            Role::GitIgnored => "90",
+        }
+    }
+}
+
+/// A named color theme: an optional truecolor palette replacing the 16 basic
+/// ANSI colors, plus per-role style overrides.
+pub struct Theme {
+    pub name: &'static str,
+    palette: Option<[(u8, u8, u8); 16]>,
+    overrides: &'static [(Role, &'static str)],
+}
+
+pub const THEMES: &[Theme] = &[
+    Theme {
+        name: "default",
+        palette: None,
+        overrides: &[],
+    },
+    Theme {
+        name: "solarized",
+        palette: Some([
+            (0x07, 0x36, 0x42),
+            (0xdc, 0x32, 0x2f),
+            (0x85, 0x99, 0x00),
+            (0xb5, 0x89, 0x00),
+            (0x26, 0x8b, 0xd2),
+            (0xd3, 0x36, 0x82),
+            (0x2a, 0xa1, 0x98),
+            (0xee, 0xe8, 0xd5),
+            (0x58, 0x6e, 0x75),
+            (0xcb, 0x4b, 0x16),
+            (0x93, 0xa1, 0x1a),
+            (0xd3, 0xa0, 0x1c),
+            (0x6c, 0x71, 0xc4),
+            (0xe0, 0x5c, 0x9c),
+            (0x4e, 0xc2, 0xb8),
+            (0xfd, 0xf6, 0xe3),
+        ]),
+        overrides: &[],
+    },
+    Theme {
+        name: "dracula",
+        palette: Some([
+            (0x21, 0x22, 0x2c),
+            (0xff, 0x55, 0x55),
+            (0x50, 0xfa, 0x7b),
+            (0xf1, 0xfa, 0x8c),
+            (0xbd, 0x93, 0xf9),
+            (0xff, 0x79, 0xc6),
+            (0x8b, 0xe9, 0xfd),
+            (0xf8, 0xf8, 0xf2),
+            (0x62, 0x72, 0xa4),
+            (0xff, 0x6e, 0x6e),
+            (0x69, 0xff, 0x94),
+            (0xff, 0xff, 0xa5),
+            (0xd6, 0xac, 0xff),
+            (0xff, 0x92, 0xdf),
+            (0xa4, 0xff, 0xff),
+            (0xff, 0xff, 0xff),
+        ]),
+        overrides: &[],
+    },
+    Theme {
+        name: "high-contrast",
+        palette: None,
+        overrides: &[
+            (Role::Dir, "1;94"),
+            (Role::DirHeader, "1;4;94"),
+            (Role::ColumnHeader, "1;4"),
+            (Role::Symlink, "1;96"),
+            (Role::SymlinkTarget, "96"),
+            (Role::Executable, "1;92"),
+            (Role::Pipe, "1;93"),
+            (Role::Socket, "1;95"),
+            (Role::Device, "1;93"),
+            (Role::Archive, "1;91"),
+            (Role::Image, "1;95"),
+            (Role::Audio, "1;96"),
+            (Role::Video, "1;95"),
+            (Role::PermDir, "1;94"),
+            (Role::PermRead, "1;93"),
+            (Role::PermWrite, "1;91"),
+            (Role::PermExec, "1;92"),
+            (Role::PermNone, "37"),
+            (Role::DirCount, "37"),
+            (Role::GitIgnored, "37"),
+            (Role::OwnerYou, "1;93"),
+            (Role::OwnerOther, "1;91"),
+        ],
+    },
+];
+
+/// How many colors the terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// Detect the color depth from COLORTERM and TERM.
+    pub fn detect() -> Self {
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColorDepth::TrueColor;
+        }
+        let term = std::env::var("TERM").unwrap_or_default();
+        if term.contains("truecolor") || term.contains("direct") {
+            ColorDepth::TrueColor
+        } else if term.contains("256") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Color {
+    /// One of the 16 basic ANSI colors (8-15 are the bright variants)
+    Basic(u8),
+    /// An xterm 256-color palette index
+    Fixed(u8),
+    Rgb(u8, u8, u8),
+}
+
+/// A parsed SGR style: text attributes plus optional colors.
+#[derive(Debug, Default)]
+struct Style {
+    attrs: Vec<u8>,
+    fg: Option<Color>,
+    bg: Option<Color>,
+}
+
+impl Style {
+    /// Parse SGR parameters such as `1;34`, `38;5;208` or `38;2;255;128;0`.
+    fn parse(sgr: &str) -> Self {
+        // Keep unparseable parameters as None so the ones after them stay
+        // in position
+        let codes: Vec<Option<u8>> = sgr.split(';').map(|c| c.parse().ok()).collect();
+        let mut style = Style::default();
+        let mut i = 0;
+        while i < codes.len() {
+            let code = match codes[i] {
+                Some(code) => code,
+                None => {
+                    i += 1;
+                    continue;
+                }
+            };
+            match code {
+                1..=9 => style.attrs.push(code),
+                30..=37 => style.fg = Some(Color::Basic(code - 30)),
+                40..=47 => style.bg = Some(Color::Basic(code - 40)),
+                90..=97 => style.fg = Some(Color::Basic(code - 90 + 8)),
+                100..=107 => style.bg = Some(Color::Basic(code - 100 + 8)),
+                38 | 48 => {
+                    let color = match codes.get(i + 1) {
+                        Some(Some(5)) => match codes.get(i + 2) {
+                            Some(&Some(n)) => {
+                                i += 2;
+                                Some(Color::Fixed(n))
+                            }
+                            _ => None,
+                        },
+                        Some(Some(2)) => match codes.get(i + 2..i + 5) {
+                            Some(&[Some(r), Some(g), Some(b)]) => {
+                                i += 4;
+                                Some(Color::Rgb(r, g, b))
+                            }
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+                    // After a malformed extended color there is no telling
+                    // where its arguments end, so ignore the rest
+                    match color {
+                        Some(color) if code == 38 => style.fg = Some(color),
+                        Some(color) => style.bg = Some(color),
+                        None => break,
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        style
+    }
+
+    /// Render back to SGR parameters, mapping basic colors through the
+    /// theme palette and downgrading to what the terminal supports.
+    fn render(&self, palette: Option<&[(u8, u8, u8); 16]>, depth: ColorDepth) -> String {
+        let mut parts: Vec<String> = self.attrs.iter().map(|a| a.to_string()).collect();
+        for (color, is_bg) in [(self.fg, false), (self.bg, true)] {
+            if let Some(color) = color {
+                parts.push(render_color(color, is_bg, palette, depth));
+            }
+        }
+        parts.join(";")
+    }
+}
+
+fn render_color(
+    color: Color,
+    is_bg: bool,
+    palette: Option<&[(u8, u8, u8); 16]>,
+    depth: ColorDepth,
+) -> String {
+    // 16-color terminals keep basic colors: their own palette is the closest
+    // match to any theme
+    let color = match (color, palette) {
+        (Color::Basic(n), Some(p)) if depth > ColorDepth::Ansi16 => {
+            let (r, g, b) = p[n as usize];
+            Color::Rgb(r, g, b)
+        }
+        _ => color,
+    };
+    let color = match (color, depth) {
+        (Color::Rgb(r, g, b), ColorDepth::Ansi256) => Color::Fixed(rgb_to_256(r, g, b)),
+        (Color::Rgb(r, g, b), ColorDepth::Ansi16) => Color::Basic(nearest_basic(r, g, b)),
+        (Color::Fixed(n), ColorDepth::Ansi16) => {
+            let (r, g, b) = fixed_to_rgb(n);
+            Color::Basic(nearest_basic(r, g, b))
+        }
+        _ => color,
+    };
+
+    let (base, bright, extended) = if is_bg { (40, 100, 48) } else { (30, 90, 38) };
+    match color {
+        Color::Basic(n) if n < 8 => (base + n as u16).to_string(),
+        Color::Basic(n) => (bright + n as u16 - 8).to_string(),
+        Color::Fixed(n) => format!("{};5;{}", extended, n),
+        Color::Rgb(r, g, b) => format!("{};2;{};{};{}", extended, r, g, b),
+    }
+}
+
+/// The xterm values of the 16 basic colors, used to pick the nearest one.
+const BASIC_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Levels of the 6x6x6 color cube in the 256-color palette.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (i32::from(x) - i32::from(y)).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+fn nearest_basic(r: u8, g: u8, b: u8) -> u8 {
+    (0..16u8)
+        .min_by_key(|&i| distance(BASIC_RGB[i as usize], (r, g, b)))
+        .unwrap_or(7)
+}
+
+/// The nearest 256-color index, from either the color cube or the gray ramp.
+fn rgb_to_256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |v: u8| -> u8 {
+        (0..6u8)
+            .min_by_key(|&i| (i32::from(CUBE_LEVELS[i as usize]) - i32::from(v)).abs())
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = 16 + 36 * ri + 6 * gi + bi;
+    let cube_rgb = fixed_to_rgb(cube);
+
+    let avg = (u16::from(r) + u16::from(g) + u16::from(b)) / 3;
+    let gray_index = ((avg.saturating_sub(8)) / 10).min(23) as u8;
+    let gray = 232 + gray_index;
+
+    if distance(fixed_to_rgb(gray), (r, g, b)) < distance(cube_rgb, (r, g, b)) {
+        gray
+    } else {
+        cube
+    }
+}
+
+fn fixed_to_rgb(n: u8) -> (u8, u8, u8) {
+    match n {
+        0..=15 => BASIC_RGB[n as usize],
+        16..=231 => {
+            let i = n - 16;
+            (
+                CUBE_LEVELS[(i / 36) as usize],
+                CUBE_LEVELS[((i / 6) % 6) as usize],
+                CUBE_LEVELS[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let v = 8 + (n - 232) * 10;
+            (v, v, v)
+        }
+    }
+}
+
+const _: () = assert!(
+    Role::all_is_indexed(),
+    "Role::ALL must list every role in declaration order"
+);
+
+/// The theme in effect, with every role's style rendered for the terminal.
+struct ActiveTheme {
+    theme: &'static Theme,
+    depth: ColorDepth,
+    roles: Vec<String>,
+}
+
+impl ActiveTheme {
+    fn new(theme: &'static Theme, depth: ColorDepth) -> Self {
+        let roles = Role::ALL
+            .iter()
+            .map(|&role| {
+                let sgr = theme
+                    .overrides
+                    .iter()
+                    .find(|(r, _)| *r == role)
+                    .map(|(_, sgr)| *sgr)
+                    .unwrap_or_else(|| role.default_sgr());
+                Style::parse(sgr).render(theme.palette.as_ref(), depth)
+            })
+            .collect();
+        ActiveTheme {
+            theme,
+            depth,
+            roles,
+        }
+    }
+}
+
+static ACTIVE: OnceLock<ActiveTheme> = OnceLock::new();
+
+fn active() -> &'static ActiveTheme {
+    ACTIVE.get_or_init(|| ActiveTheme::new(&THEMES[0], ColorDepth::detect()))
+}
+
+/// Select the theme from `--theme` and detect the terminal's color depth.
+pub fn init(args: &Args) {
+    let theme = args
+        .theme
+        .as_deref()
+        .and_then(|name| THEMES.iter().find(|t| t.name == name))
+        .unwrap_or(&THEMES[0]);
+    let _ = ACTIVE.set(ActiveTheme::new(theme, ColorDepth::detect()));
+}
+
+/// The SGR parameters for a role under the active theme.
+pub fn sgr(role: Role) -> &'static str {
+    &active().roles[role as usize]
+}
+
+/// Wrap `text` in the active theme's style for `role`.
+pub fn paint(role: Role, text: &str) -> String {
+    format!("\x1b[{}m{}\x1b[0m", sgr(role), text)
+}
+
+/// Wrap `text` in a literal SGR style (such as an icon color), recolored by
+/// the active theme.
+pub fn paint_sgr(sgr: &str, text: &str) -> String {
+    let active = active();
+    let extended = sgr.contains("38;") || sgr.contains("48;");
+    if active.theme.palette.is_none() && (!extended || active.depth == ColorDepth::TrueColor) {
+        return format!("\x1b[{}m{}\x1b[0m", sgr, text);
+    }
+    let style = Style::parse(sgr).render(active.theme.palette.as_ref(), active.depth);
+    format!("\x1b[{}m{}\x1b[0m", style, text)
+}
+
+/// Wrap `text` in the color `t` of the way (0.0 to 1.0) from `cold` to
+/// `hot`, downgraded to the terminal's color depth.
+pub fn paint_gradient(cold: (u8, u8, u8), hot: (u8, u8, u8), t: f64, text: &str) -> String {
+    let t = t.clamp(0.0, 1.0);
+    let mix = |a: u8, b: u8| (f64::from(a) + (f64::from(b) - f64::from(a)) * t).round() as u8;
+    let sgr = format!(
+        "38;2;{};{};{}",
+        mix(cold.0, hot.0),
+        mix(cold.1, hot.1),
+        mix(cold.2, hot.2)
+    );
+    paint_sgr(&sgr, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn style_parsing() {
+        let cases = [
+            ("", ""),
+            ("0", ""),
+            ("01;34", "1;34"),
+            ("1;4;31;42", "1;4;31;42"),
+            ("91;104", "91;104"),
+            ("38;5;208", "38;5;208"),
+            ("48;5;16;1", "1;48;5;16"),
+            ("38;2;255;128;0", "38;2;255;128;0"),
+            ("1;38;2;1;2;3;48;5;4", "1;38;2;1;2;3;48;5;4"),
+            ("31;32", "32"),
+            // Malformed input
+            ("x;34", "34"),
+            ("1;;34", "1;34"),
+            ("300;34", "34"),
+            ("38;5;x;1", ""),
+            ("38;5;300;1", ""),
+            ("1;38;5", "1"),
+            ("34;38;2;1;2", "34"),
+            ("38;2;1;x;3;4", ""),
+            ("38;9;1", ""),
+            ("38", ""),
+        ];
+        for (sgr, expected) in cases {
+            let rendered = Style::parse(sgr).render(None, ColorDepth::TrueColor);
+            assert_eq!(rendered, expected, "Style::parse({:?})", sgr);
+        }
+    }
+
+    #[test]
+    fn basic_color_matching() {
+        let cases = [
+            ((0, 0, 0), 0),
+            ((200, 0, 0), 1),
+            ((250, 10, 10), 9),
+            ((0, 0, 255), 4),
+            ((128, 128, 128), 8),
+            ((230, 230, 230), 7),
+            ((255, 255, 255), 15),
+        ];
+        for ((r, g, b), expected) in cases {
+            assert_eq!(
+                nearest_basic(r, g, b),
+                expected,
+                "nearest_basic({}, {}, {})",
+                r,
+                g,
+                b
+            );
+        }
+    }
+
+    #[test]
+    fn fixed_color_matching() {
+        let cases = [
+            ((0, 0, 0), 16),
+            ((255, 255, 255), 231),
+            ((255, 0, 0), 196),
+            ((95, 135, 175), 67),
+            ((255, 128, 0), 208),
+            ((18, 18, 18), 233),
+            ((128, 128, 128), 244),
+            ((238, 238, 238), 255),
+        ];
+        for ((r, g, b), expected) in cases {
+            assert_eq!(
+                rgb_to_256(r, g, b),
+                expected,
+                "rgb_to_256({}, {}, {})",
+                r,
+                g,
+                b
+            );
+        }
+        for n in 16..=255 {
+            let (r, g, b) = fixed_to_rgb(n);
+            assert_eq!(
+                fixed_to_rgb(rgb_to_256(r, g, b)),
+                (r, g, b),
+                "round trip of {}",
+                n
+            );
+        }
+    }
+}
@@ -0,0 +1,400 @@
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+/// Extended attribute holding a POSIX access ACL.
+pub const ACL_ACCESS: &str = "system.posix_acl_access";
+/// Extended attribute holding a directory's default POSIX ACL.
+pub const ACL_DEFAULT: &str = "system.posix_acl_default";
+/// Extended attribute holding the SELinux security context.
+pub const SELINUX: &str = "security.selinux";
+/// Extended attribute holding Linux file capabilities.
+pub const CAPABILITY: &str = "security.capability";
+
+/// Capability names indexed by bit number, as in `<linux/capability.h>`.
+const CAP_NAMES: [&str; 41] = [
+    "cap_chown",
+    "cap_dac_override",
+    "cap_dac_read_search",
+    "cap_fowner",
+    "cap_fsetid",
+    "cap_kill",
+    "cap_setgid",
+    "cap_setuid",
+    "cap_setpcap",
+    "cap_linux_immutable",
+    "cap_net_bind_service",
+    "cap_net_broadcast",
+    "cap_net_admin",
+    "cap_net_raw",
+    "cap_ipc_lock",
+    "cap_ipc_owner",
+    "cap_sys_module",
+    "cap_sys_rawio",
+    "cap_sys_chroot",
+    "cap_sys_ptrace",
+    "cap_sys_pacct",
+    "cap_sys_admin",
+    "cap_sys_boot",
+    "cap_sys_nice",
+    "cap_sys_resource",
+    "cap_sys_time",
+    "cap_sys_tty_config",
+    "cap_mknod",
+    "cap_lease",
+    "cap_audit_write",
+    "cap_audit_control",
+    "cap_setfcap",
+    "cap_mac_override",
+    "cap_mac_admin",
+    "cap_syslog",
+    "cap_wake_alarm",
+    "cap_block_suspend",
+    "cap_audit_read",
+    "cap_perfmon",
+    "cap_bpf",
+    "cap_checkpoint_restore",
+];
+
+/// List the extended attribute names of a file, without following symlinks.
+/// Returns an empty list if the file has none or the filesystem lacks support.
+pub fn list(path: &Path) -> Vec<String> {
+    let cpath = match CString::new(path.as_os_str().as_bytes()) {
+        Ok(p) => p,
+        Err(_) => return Vec::new(),
+    };
+
+    let size = unsafe { sys_list(&cpath, std::ptr::null_mut(), 0) };
+    if size <= 0 {
+        return Vec::new();
+    }
+
+    let mut buf: Vec<u8> = vec![0; size as usize];
+    let size = unsafe { sys_list(&cpath, buf.as_mut_ptr().cast(), buf.len()) };
+    if size <= 0 {
+        return Vec::new();
+    }
+    buf.truncate(size as usize);
+
+    // Names are NUL-terminated and packed back to back
+    buf.split(|&b| b == 0)
+        .filter(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+        .collect()
+}
+
+/// Read the value of one extended attribute, without following symlinks.
+pub fn get(path: &Path, name: &str) -> Option<Vec<u8>> {
+    let cpath = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let cname = CString::new(name).ok()?;
+
+    let size = unsafe { sys_get(&cpath, &cname, std::ptr::null_mut(), 0) };
+    if size < 0 {
+        return None;
+    }
+
+    let mut buf: Vec<u8> = vec![0; size as usize];
+    let size = unsafe { sys_get(&cpath, &cname, buf.as_mut_ptr().cast(), buf.len()) };
+    if size < 0 {
+        return None;
+    }
+    buf.truncate(size as usize);
+    Some(buf)
+}
+
+#[cfg(target_os = "linux")]
+unsafe fn sys_list(path: &CString, buf: *mut libc::c_char, size: usize) -> isize {
+    libc::llistxattr(path.as_ptr(), buf, size)
+}
+
+#[cfg(target_os = "linux")]
+unsafe fn sys_get(path: &CString, name: &CString, buf: *mut libc::c_void, size: usize) -> isize {
+    libc::lgetxattr(path.as_ptr(), name.as_ptr(), buf, size)
+}
+
+#[cfg(target_os = "macos")]
+unsafe fn sys_list(path: &CString, buf: *mut libc::c_char, size: usize) -> isize {
+    libc::listxattr(path.as_ptr(), buf, size, libc::XATTR_NOFOLLOW)
+}
+
+#[cfg(target_os = "macos")]
+unsafe fn sys_get(path: &CString, name: &CString, buf: *mut libc::c_void, size: usize) -> isize {
+    libc::getxattr(
+        path.as_ptr(),
+        name.as_ptr(),
+        buf,
+        size,
+        0,
+        libc::XATTR_NOFOLLOW,
+    )
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+unsafe fn sys_list(_path: &CString, _buf: *mut libc::c_char, _size: usize) -> isize {
+    -1
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+unsafe fn sys_get(_: &CString, _: &CString, _: *mut libc::c_void, _: usize) -> isize {
+    -1
+}
+
+/// The character shown after the permission bits in long view:
+/// `+` for a POSIX ACL, `.` for an SELinux context only, `@` for other
+/// extended attributes.
+pub fn indicator(names: &[String]) -> Option<char> {
+    if names.iter().any(|n| n == ACL_ACCESS || n == ACL_DEFAULT) {
+        Some('+')
+    } else if names.iter().any(|n| n != SELINUX) {
+        Some('@')
+    } else if !names.is_empty() {
+        Some('.')
+    } else {
+        None
+    }
+}
+
+/// Render an attribute value for `--xattr=values`: text if it is printable
+/// UTF-8, otherwise hex. Long values are cut off.
+pub fn format_value(value: &[u8]) -> String {
+    const MAX_SHOWN: usize = 64;
+    let shown = &value[..value.len().min(MAX_SHOWN)];
+    let more = if value.len() > MAX_SHOWN { "..." } else { "" };
+
+    // Many text values carry a trailing NUL
+    let text = shown.strip_suffix(&[0]).unwrap_or(shown);
+    match std::str::from_utf8(text) {
+        Ok(s) if s.chars().all(|c| !c.is_control()) => format!("\"{}\"{}", s, more),
+        _ => {
+            let hex: String = shown.iter().map(|b| format!("{:02x}", b)).collect();
+            format!("0x{}{}", hex, more)
+        }
+    }
+}
+
+/// Decode a `system.posix_acl_*` value into getfacl-style lines such as
+/// `user:alice:r-x`. Returns None if the value is malformed.
+pub fn decode_acl(
+    value: &[u8],
+    prefix: &str,
+    user_cache: &mut HashMap<u32, String>,
+    group_cache: &mut HashMap<u32, String>,
+) -> Option<Vec<String>> {
+    const ACL_VERSION: u32 = 2;
+    const USER_OBJ: u16 = 0x01;
+    const USER: u16 = 0x02;
+    const GROUP_OBJ: u16 = 0x04;
+    const GROUP: u16 = 0x08;
+    const MASK: u16 = 0x10;
+    const OTHER: u16 = 0x20;
+
+    let header = value.get(..4)?;
+    if u32::from_le_bytes(header.try_into().ok()?) != ACL_VERSION || (value.len() - 4) % 8 != 0 {
+        return None;
+    }
+
+    let mut lines = Vec::new();
+    for chunk in value[4..].chunks_exact(8) {
+        let tag = u16::from_le_bytes([chunk[0], chunk[1]]);
+        let perm = u16::from_le_bytes([chunk[2], chunk[3]]);
+        let id = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+
+        let qualifier = match tag {
+            USER_OBJ => "user::".to_string(),
+            USER => format!("user:{}:", crate::entry::get_username(id, user_cache)),
+            GROUP_OBJ => "group::".to_string(),
+            GROUP => format!("group:{}:", crate::entry::get_groupname(id, group_cache)),
+            MASK => "mask::".to_string(),
+            OTHER => "other::".to_string(),
+            _ => return None,
+        };
+        let rwx: String = [(4, 'r'), (2, 'w'), (1, 'x')]
+            .iter()
+            .map(|&(bit, c)| if perm & bit != 0 { c } else { '-' })
+            .collect();
+        lines.push(format!("{}{}{}", prefix, qualifier, rwx));
+    }
+    Some(lines)
+}
+
+/// Decode a `security.capability` value (v1, v2 or v3) into getcap-style
+/// text such as `cap_net_admin,cap_net_raw=ep`. Capabilities sharing the same
+/// flags are grouped. Returns None if the value is malformed.
+pub fn decode_capabilities(value: &[u8]) -> Option<String> {
+    const VERSION_MASK: u32 = 0xFF00_0000;
+    const VERSION_1: u32 = 0x0100_0000;
+    const VERSION_2: u32 = 0x0200_0000;
+    const VERSION_3: u32 = 0x0300_0000;
+    const FLAG_EFFECTIVE: u32 = 0x0000_0001;
+
+    let word = |i: usize| -> Option<u32> {
+        let bytes = value.get(i * 4..i * 4 + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?))
+    };
+
+    let magic = word(0)?;
+    let sets = match magic & VERSION_MASK {
+        VERSION_1 => 1,
+        VERSION_2 | VERSION_3 => 2,
+        _ => return None,
+    };
+    let effective = magic & FLAG_EFFECTIVE != 0;
+
+    // (permitted, inheritable) bit masks, 32 capabilities per set
+    let mut permitted = 0u64;
+    let mut inheritable = 0u64;
+    for set in 0..sets {
+        permitted |= u64::from(word(1 + set * 2)?) << (32 * set);
+        inheritable |= u64::from(word(2 + set * 2)?) << (32 * set);
+    }
+
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    for bit in 0..64 {
+        let p = permitted & (1 << bit) != 0;
+        let i = inheritable & (1 << bit) != 0;
+        if !p && !i {
+            continue;
+        }
+
+        let mut flags = String::new();
+        if effective {
+            flags.push('e');
+        }
+        if i {
+            flags.push('i');
+        }
+        if p {
+            flags.push('p');
+        }
+        let name = CAP_NAMES
+            .get(bit)
+            .map(|n| n.to_string())
+            .unwrap_or_else(|| format!("cap_{}", bit));
+
+        match groups.iter_mut().find(|(f, _)| *f == flags) {
+            Some((_, names)) => names.push(name),
+            None => groups.push((flags, vec![name])),
+        }
+    }
+
+    let text: Vec<String> = groups
+        .iter()
+        .map(|(flags, names)| format!("{}={}", names.join(","), flags))
+        .collect();
+    Some(text.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a POSIX ACL xattr value from (tag, perm, id) entries.
+    fn acl(entries: &[(u16, u16, u32)]) -> Vec<u8> {
+        let mut value = 2u32.to_le_bytes().to_vec();
+        for &(tag, perm, id) in entries {
+            value.extend_from_slice(&tag.to_le_bytes());
+            value.extend_from_slice(&perm.to_le_bytes());
+            value.extend_from_slice(&id.to_le_bytes());
+        }
+        value
+    }
+
+    fn decode(value: &[u8], prefix: &str) -> Option<Vec<String>> {
+        let mut users = HashMap::from([(1000, "alice".to_string())]);
+        let mut groups = HashMap::from([(100, "staff".to_string())]);
+        decode_acl(value, prefix, &mut users, &mut groups)
+    }
+
+    #[test]
+    fn acl_entries() {
+        let value = acl(&[
+            (0x01, 7, u32::MAX),
+            (0x02, 5, 1000),
+            (0x04, 4, u32::MAX),
+            (0x08, 6, 100),
+            (0x10, 7, u32::MAX),
+            (0x20, 0, u32::MAX),
+        ]);
+        assert_eq!(
+            decode(&value, "").unwrap(),
+            [
+                "user::rwx",
+                "user:alice:r-x",
+                "group::r--",
+                "group:staff:rw-",
+                "mask::rwx",
+                "other::---"
+            ]
+        );
+        assert_eq!(
+            decode(&acl(&[(0x01, 6, u32::MAX)]), "default:").unwrap(),
+            ["default:user::rw-"]
+        );
+        assert_eq!(decode(&acl(&[]), "").unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn malformed_acls() {
+        let mut trailing = acl(&[(0x01, 7, u32::MAX)]);
+        trailing.push(0);
+        let mut version_1 = acl(&[(0x01, 7, u32::MAX)]);
+        version_1[0] = 1;
+
+        assert_eq!(decode(&[], ""), None);
+        assert_eq!(decode(&[2, 0, 0], ""), None);
+        assert_eq!(decode(&trailing, ""), None);
+        assert_eq!(decode(&version_1, ""), None);
+        assert_eq!(decode(&acl(&[(0x40, 7, u32::MAX)]), ""), None);
+    }
+
+    /// Build a `security.capability` value from its 32-bit words.
+    fn caps(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn capability_sets() {
+        let net_admin_raw = (1 << 12) | (1 << 13);
+        let cases = [
+            (
+                caps(&[0x0200_0001, 1 << 10, 0, 0, 0]),
+                "cap_net_bind_service=ep",
+            ),
+            (
+                caps(&[0x0200_0001, net_admin_raw, 0, 0, 0]),
+                "cap_net_admin,cap_net_raw=ep",
+            ),
+            (
+                caps(&[0x0200_0000, 1 << 10, 0, 0, 0]),
+                "cap_net_bind_service=p",
+            ),
+            (
+                caps(&[0x0200_0001, 1 << 12, net_admin_raw, 0, 0]),
+                "cap_net_admin=eip cap_net_raw=ei",
+            ),
+            (
+                caps(&[0x0200_0000, 0, 0, 1 << 6, 1 << 13]),
+                "cap_perfmon=p cap_45=i",
+            ),
+            (caps(&[0x0100_0001, 1, 0]), "cap_chown=ep"),
+            (
+                caps(&[0x0300_0001, 1 << 21, 0, 0, 0, 1000]),
+                "cap_sys_admin=ep",
+            ),
+            (caps(&[0x0200_0000, 0, 0, 0, 0]), ""),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(decode_capabilities(&value).as_deref(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn malformed_capabilities() {
+        assert_eq!(decode_capabilities(&[]), None);
+        assert_eq!(decode_capabilities(&[1, 0]), None);
+        assert_eq!(decode_capabilities(&caps(&[0x0100_0001, 1])), None);
+        assert_eq!(decode_capabilities(&caps(&[0x0200_0001, 1, 0])), None);
+        assert_eq!(decode_capabilities(&caps(&[0x0400_0001, 1, 0, 0, 0])), None);
+    }
+}